@@ -0,0 +1,145 @@
+use std::{num::NonZeroU64, sync::Arc};
+
+use zarrs::{
+    array::{Array, ArrayMetadata},
+    node::NodePath,
+    storage::{ReadableStorageTraits, WritableStorageTraits},
+};
+
+use crate::{
+    N5ArrayMetadata, N5ArrayMode, N5Compression, N5EdgeBlockPolicy, N5StoreAdapter,
+    convert::meta_key_n5, hierarchy::write_n5_metadata,
+};
+
+/// A builder for N5 arrays, configured in N5 terms.
+///
+/// [Self::build] writes the N5 `attributes.json` and returns a [zarrs] [Array]
+/// over an [N5StoreAdapter], with the [crate::N5DefaultCodec] configured for the given compression.
+///
+/// ```
+/// # use std::{num::NonZeroU64, sync::Arc};
+/// # use zarrs::storage::store::MemoryStore;
+/// # use zarrs_n5::{N5ArrayBuilder, N5Compression, N5StoreAdapter};
+/// let store = Arc::new(N5StoreAdapter::new(MemoryStore::default()));
+/// let block_size = vec![NonZeroU64::new(64).unwrap(); 2];
+/// let array = N5ArrayBuilder::new(vec![100, 100], block_size, "uint16")
+///     .compression(N5Compression::Gzip {
+///         level: 6,
+///         use_zlib: false,
+///     })
+///     .build(store, "/data")
+///     .unwrap();
+/// array.store_chunk(&[0, 0], vec![1u16; 64 * 64]).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct N5ArrayBuilder {
+    dimensions: Vec<u64>,
+    block_size: Vec<NonZeroU64>,
+    data_type: String,
+    compression: N5Compression,
+    attributes: serde_json::Map<String, serde_json::Value>,
+    edge_block_policy: N5EdgeBlockPolicy,
+}
+
+impl N5ArrayBuilder {
+    /// Create a builder for an uncompressed array with the given
+    /// `dimensions`, `blockSize` and `dataType` (e.g. `"uint8"`, `"float32"`).
+    pub fn new(
+        dimensions: Vec<u64>,
+        block_size: Vec<NonZeroU64>,
+        data_type: impl Into<String>,
+    ) -> Self {
+        Self {
+            dimensions,
+            block_size,
+            data_type: data_type.into(),
+            compression: N5Compression::default(),
+            attributes: Default::default(),
+            edge_block_policy: N5EdgeBlockPolicy::default(),
+        }
+    }
+
+    /// Set the block compression.
+    pub fn compression(&mut self, compression: N5Compression) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the initial attributes.
+    pub fn attributes(
+        &mut self,
+        attributes: serde_json::Map<String, serde_json::Value>,
+    ) -> &mut Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Mutable access to the initial attributes.
+    pub fn attributes_mut(&mut self) -> &mut serde_json::Map<String, serde_json::Value> {
+        &mut self.attributes
+    }
+
+    /// Set how edge blocks are written by the returned array.
+    pub fn edge_block_policy(&mut self, policy: N5EdgeBlockPolicy) -> &mut Self {
+        self.edge_block_policy = policy;
+        self
+    }
+
+    /// Validate the configuration and produce the N5 array metadata.
+    ///
+    /// Fails if the dimensionalities do not match,
+    /// or the compression is invalid for the data type (see [N5Compression::to_bytes_to_bytes_codec_for_data_type])
+    /// or cannot be encoded (see [N5Compression::can_encode]).
+    pub fn build_metadata(&self) -> crate::Result<N5ArrayMetadata> {
+        if self.dimensions.len() != self.block_size.len() {
+            return Err(crate::Error::general(format!(
+                "dimensions {:?} and block size {:?} have different lengths",
+                self.dimensions, self.block_size
+            )));
+        }
+        self.compression
+            .to_bytes_to_bytes_codec_for_data_type(&self.data_type)
+            .map_err(|e| {
+                crate::Error::general(format!(
+                    "cannot write N5 compression {:?}: {e}",
+                    self.compression
+                ))
+            })?;
+        if !self.compression.can_encode() {
+            return Err(crate::Error::general(format!(
+                "cannot write N5 compression {:?}: it is decode-only",
+                self.compression
+            )));
+        }
+        Ok(N5ArrayMetadata {
+            n5_version: None,
+            dimensions: self.dimensions.clone(),
+            block_size: self.block_size.clone(),
+            data_type: self.data_type.clone(),
+            compression: self.compression.clone(),
+            attributes: self.attributes.clone(),
+        })
+    }
+
+    /// Write the array's `attributes.json` and return the array.
+    ///
+    /// Metadata is merged into any existing `attributes.json` (see [crate::N5Metadata::merge_into]),
+    /// so an array can be created at an existing hierarchy root,
+    /// but not over an existing array with a different structure.
+    pub fn build<S: ReadableStorageTraits + WritableStorageTraits + 'static>(
+        &self,
+        store: Arc<N5StoreAdapter<S>>,
+        path: &str,
+    ) -> crate::Result<Array<N5StoreAdapter<S>>> {
+        let node_path = NodePath::new(path).map_err(crate::Error::wrap)?;
+        let metadata = self.build_metadata()?;
+        let zarr_metadata = metadata
+            .clone()
+            .try_into_zarr_with_edge_block_policy(N5ArrayMode::Default, self.edge_block_policy)?;
+        let array = Array::new_with_metadata(store.clone(), path, ArrayMetadata::V3(zarr_metadata))
+            .map_err(crate::Error::wrap)?;
+        write_n5_metadata(store.inner(), &meta_key_n5(&node_path), metadata)
+            .map_err(crate::Error::wrap)?;
+        Ok(array)
+    }
+}
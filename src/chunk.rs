@@ -1,8 +1,11 @@
+use std::num::NonZeroU64;
+
 /// Representation of the N5 block header.
 #[derive(Debug, Clone)]
 pub struct N5BlockHeader {
     pub(crate) mode: N5BlockMode,
     /// Column-major, probably?
+    /// Empty for object-mode blocks, whose header has no shape.
     pub(crate) shape: Vec<u32>,
 }
 
@@ -11,56 +14,105 @@ pub struct N5BlockHeader {
 #[repr(u16)]
 pub enum N5BlockMode {
     Default = 0,
-    #[allow(unused)]
     VarLength {
         num_el: u32,
     } = 1,
-    Object = 2,
+    /// An opaque payload of `num_el` bytes.
+    Object {
+        num_el: u32,
+    } = 2,
 }
 
 impl N5BlockHeader {
+    /// Create the header for a default-mode block of the given shape.
+    pub fn new_default(shape: &[NonZeroU64]) -> crate::Result<Self> {
+        let shape = shape
+            .iter()
+            .map(|n| {
+                u32::try_from(n.get()).map_err(|_| {
+                    crate::Error::general(format!("N5 block dimension {n} does not fit in a u32"))
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        if shape.len() > u16::MAX as usize {
+            return Err(crate::Error::general(format!(
+                "N5 block dimensionality {} does not fit in a u16",
+                shape.len()
+            )));
+        }
+        Ok(Self {
+            mode: N5BlockMode::Default,
+            shape,
+        })
+    }
+
+    /// Parse a header from the start of a block.
     pub fn from_bytes(bytes: &[u8]) -> crate::Result<Self> {
         let mut offset: usize = 0;
+        let mut take = |n: usize| {
+            let out = bytes.get(offset..offset + n).ok_or_else(|| {
+                crate::Error::general(format!(
+                    "N5 block header truncated at {} bytes",
+                    bytes.len()
+                ))
+            });
+            offset += n;
+            out
+        };
 
-        let mode_num = u16::from_be_bytes(
-            bytes[offset..offset + 2]
-                .try_into()
-                .map_err(crate::Error::wrap)?,
-        );
-        offset += 2;
-        let ndim = u16::from_be_bytes(
-            bytes[offset..offset + 2]
-                .try_into()
-                .map_err(crate::Error::wrap)?,
-        );
-        offset += 2;
+        let mode_num = u16::from_be_bytes(take(2)?.try_into().map_err(crate::Error::wrap)?);
+        if mode_num == 2 {
+            let num_el = u32::from_be_bytes(take(4)?.try_into().map_err(crate::Error::wrap)?);
+            return Ok(N5BlockHeader {
+                mode: N5BlockMode::Object { num_el },
+                shape: Vec::default(),
+            });
+        }
+        let ndim = u16::from_be_bytes(take(2)?.try_into().map_err(crate::Error::wrap)?);
         let mut shape = Vec::with_capacity(ndim as usize);
         for _ in 0..ndim {
             shape.push(u32::from_be_bytes(
-                bytes[offset..offset + 4]
-                    .try_into()
-                    .map_err(crate::Error::wrap)?,
+                take(4)?.try_into().map_err(crate::Error::wrap)?,
             ));
-            offset += 4;
         }
 
         let mode = match mode_num {
             0 => N5BlockMode::Default,
             1 => {
-                let num_el = u32::from_be_bytes(
-                    bytes[offset..offset + 4]
-                        .try_into()
-                        .map_err(crate::Error::wrap)?,
-                );
+                let num_el = u32::from_be_bytes(take(4)?.try_into().map_err(crate::Error::wrap)?);
                 N5BlockMode::VarLength { num_el }
             }
-            2 => N5BlockMode::Object,
             n => return Err(crate::Error::general(format!("invalid N5 chunk mode {n}"))),
         };
         Ok(N5BlockHeader { mode, shape })
     }
 
+    /// Serialize the header as big-endian bytes, ready to be followed by the block body.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data_offset());
+        let mode_num: u16 = match self.mode {
+            N5BlockMode::Default => 0,
+            N5BlockMode::VarLength { .. } => 1,
+            N5BlockMode::Object { .. } => 2,
+        };
+        out.extend_from_slice(&mode_num.to_be_bytes());
+        if !matches!(self.mode, N5BlockMode::Object { .. }) {
+            out.extend_from_slice(&(self.shape.len() as u16).to_be_bytes());
+            for s in &self.shape {
+                out.extend_from_slice(&s.to_be_bytes());
+            }
+        }
+        if let N5BlockMode::VarLength { num_el } | N5BlockMode::Object { num_el } = self.mode {
+            out.extend_from_slice(&num_el.to_be_bytes());
+        }
+        out
+    }
+
     pub(crate) fn data_offset(&self) -> usize {
+        if matches!(self.mode, N5BlockMode::Object { .. }) {
+            return size_of::<u16>()  // mode discriminator
+                + size_of::<u32>(); // num_el
+        }
         size_of::<u16>()  // mode discriminator
             + size_of::<u16>() // ndim
             + self.shape.len() * size_of::<u32>()  // shape
@@ -1,23 +1,33 @@
 use std::borrow::Cow;
+use std::num::{NonZeroU32, NonZeroU64};
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use zarrs::array::codec::api::{
-    ArrayBytes, ArrayBytesRaw, ArrayCodecTraits, ArrayToBytesCodecTraits, BytesRepresentation,
-    BytesToBytesCodecTraits, Codec, CodecError, CodecMetadataOptions, CodecOptions, CodecPluginV3,
-    CodecTraits, CodecTraitsV3, PartialDecoderCapability, PartialEncoderCapability,
-    RecommendedConcurrency,
+    ArrayBytes, ArrayBytesRaw, ArrayCodecTraits, ArrayPartialDecoderTraits,
+    ArrayPartialEncoderTraits, ArrayToBytesCodecTraits, BytesPartialDecoderTraits,
+    BytesPartialEncoderTraits, BytesRepresentation, BytesToBytesCodecTraits, Codec, CodecError,
+    CodecMetadataOptions, CodecOptions, CodecPluginV3, CodecTraits, CodecTraitsV3,
+    PartialDecoderCapability, PartialEncoderCapability, RecommendedConcurrency,
+};
+#[cfg(feature = "async")]
+use zarrs::array::codec::api::{
+    AsyncArrayPartialDecoderTraits, AsyncArrayPartialEncoderTraits, AsyncBytesPartialDecoderTraits,
+    AsyncBytesPartialEncoderTraits,
 };
 use zarrs::array::codec::{BytesCodec, TransposeOrder};
 use zarrs::array::{CodecChain, codec::TransposeCodec};
 use zarrs::metadata::v3::MetadataV3;
 use zarrs::plugin::PluginCreateError;
 
+use super::N5StringCodec;
 use crate::chunk::{N5BlockHeader, N5BlockMode};
 
-// TODO
-// ?lz4
-// ?xz
+mod detect;
+use detect::DetectedCompression;
+
+mod partial;
+use partial::N5DefaultCodecPartial;
 
 zarrs::plugin::impl_extension_aliases!(N5DefaultCodec, v3: "n5_default", ["zarrs.n5_default"]);
 inventory::submit! {
@@ -26,7 +36,16 @@ inventory::submit! {
 
 /// Codec for N5 blocks.
 ///
-/// Validates and strips the header, then applies big-endian byte order and the configured compression codec, if any.
+/// On decode, validates and strips the header, then applies big-endian byte order and the configured compression codec, if any.
+/// On encode, does the reverse, writing a default-mode header.
+/// Each block is decoded according to the mode in its header: varlength blocks (which n5-java writes
+/// whenever a block's element count differs from its shape) fill their header shape in N5 order
+/// with their elements, and the fill value beyond them.
+/// Edge blocks are truncated to the array bounds unless configured otherwise (see [N5EdgeBlockPolicy]).
+/// Optionally, the compression of each block can be detected when decoding (see [N5DefaultCodec::with_compression_detection]).
+/// Blocks containing only the fill value are never passed to the codec by [zarrs::array::Array],
+/// which erases them instead, unless the `store_empty_chunks` codec option is set;
+/// N5 readers treat these missing blocks as zeros.
 /// Should not be used with any other codecs.
 #[derive(Debug, Clone)]
 pub struct N5DefaultCodec {
@@ -35,26 +54,315 @@ pub struct N5DefaultCodec {
     ///
     /// These codecs are only applied to the N5 block body, i.e. not the block header.
     codecs: CodecChain,
+    /// If present, edge blocks are padded to this shape when encoding.
+    /// Otherwise, they are truncated to the array bounds.
+    padded_block_size: Option<Vec<NonZeroU64>>,
+    /// If present, the number of threads the compression may use for each block.
+    concurrency: Option<NonZeroU32>,
+    /// Whether to detect each block's compression from its magic bytes when decoding.
+    detect_compression: bool,
+}
+
+/// How to lay out blocks which overhang the upper bounds of the array when writing.
+///
+/// Both layouts can be read regardless of this setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum N5EdgeBlockPolicy {
+    /// Truncate edge blocks to the in-bounds region, as n5-java does.
+    #[default]
+    Truncate,
+    /// Pad edge blocks with the fill value up to the full block size.
+    Pad,
 }
 
 impl N5DefaultCodec {
     pub fn new(compression: Option<Arc<dyn BytesToBytesCodecTraits>>, ndim: usize) -> Self {
+        Self::new_with_array_to_bytes(compression, ndim, Arc::new(BytesCodec::big()))
+    }
+
+    /// Create a codec for the N5 `string` data type,
+    /// whose elements are serialized as NUL-separated UTF-8 (see [N5StringCodec]).
+    pub fn new_string(compression: Option<Arc<dyn BytesToBytesCodecTraits>>, ndim: usize) -> Self {
+        Self::new_with_array_to_bytes(compression, ndim, Arc::new(N5StringCodec::new()))
+    }
+
+    fn new_with_array_to_bytes(
+        compression: Option<Arc<dyn BytesToBytesCodecTraits>>,
+        ndim: usize,
+        array_to_bytes: Arc<dyn ArrayToBytesCodecTraits>,
+    ) -> Self {
         let transpose_order = (0..ndim).rev().collect::<Vec<_>>();
         let codecs = CodecChain::new(
             vec![Arc::new(TransposeCodec::new(
                 TransposeOrder::new(&transpose_order).unwrap(),
             ))],
-            Arc::new(BytesCodec::big()),
+            array_to_bytes,
             compression.into_iter().collect(),
         );
-        Self { codecs }
+        Self {
+            codecs,
+            padded_block_size: None,
+            concurrency: None,
+            detect_compression: false,
+        }
+    }
+
+    /// Pad edge blocks to the given block size when encoding,
+    /// rather than truncating them to the array bounds.
+    pub fn with_padded_edge_blocks(mut self, block_size: Vec<NonZeroU64>) -> Self {
+        self.padded_block_size = Some(block_size);
+        self
+    }
+
+    /// Allow the compression to use up to this many threads for each block,
+    /// which zarrs takes into account when dividing threads between blocks.
+    ///
+    /// This is the equivalent of Blosc's `nthreads` (see [crate::N5Compression::concurrency]).
+    pub fn with_concurrency(mut self, concurrency: NonZeroU32) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Detect the compression of each block from its magic bytes when decoding,
+    /// for arrays whose blocks were not all written with the compression in the metadata.
+    ///
+    /// gzip, zlib, bzip2, zstd, Blosc and LZ4 are recognised,
+    /// as are uncompressed blocks of exactly the expected length.
+    /// Blocks which are not recognised, or which fail to decode as the detected compression,
+    /// are decoded with the configured compression.
+    /// A warning is logged for each block decoded with a different compression.
+    pub fn with_compression_detection(mut self, detect_compression: bool) -> Self {
+        self.detect_compression = detect_compression;
+        self
+    }
+
+    /// Apply the given edge block policy; `block_size` is only used when padding.
+    pub fn with_edge_block_policy(
+        self,
+        policy: N5EdgeBlockPolicy,
+        block_size: &[NonZeroU64],
+    ) -> Self {
+        match policy {
+            N5EdgeBlockPolicy::Truncate => self,
+            N5EdgeBlockPolicy::Pad => self.with_padded_edge_blocks(block_size.to_vec()),
+        }
+    }
+
+    /// The edge block policy used when encoding.
+    pub fn edge_block_policy(&self) -> N5EdgeBlockPolicy {
+        if self.padded_block_size.is_some() {
+            N5EdgeBlockPolicy::Pad
+        } else {
+            N5EdgeBlockPolicy::Truncate
+        }
     }
 
     pub fn new_with_configuration(
         configuration: &N5DefaultCodecConfiguration,
     ) -> Result<Self, PluginCreateError> {
         let codecs = CodecChain::from_metadata(&configuration.codecs)?;
-        Ok(Self { codecs })
+        Ok(Self {
+            codecs,
+            padded_block_size: configuration.padded_block_size.clone(),
+            concurrency: configuration.concurrency,
+            detect_compression: configuration.detect_compression,
+        })
+    }
+
+    /// Whether subsets of blocks can be read by byte range rather than decoded whole,
+    /// i.e. blocks are uncompressed and have fixed-size elements.
+    ///
+    /// With compression detection, blocks of an uncompressed array may still be compressed, so are always decoded whole.
+    fn supports_ranged_reads(&self) -> bool {
+        self.codecs.bytes_to_bytes_codecs().is_empty()
+            && !self.detect_compression
+            && !self.is_string()
+    }
+
+    /// Whether the elements are N5 strings.
+    fn is_string(&self) -> bool {
+        self.codecs
+            .array_to_bytes_codec()
+            .as_any()
+            .is::<N5StringCodec>()
+    }
+
+    /// Encode a chunk as a block with the given header shape,
+    /// which must be at least as large as the chunk in every dimension.
+    ///
+    /// Like n5-java, string blocks are written in varlength mode (counting bytes rather than elements)
+    /// unless their serialized length happens to match the number of elements.
+    fn encode_block<'a>(
+        &self,
+        bytes: ArrayBytes<'a>,
+        shape: &[NonZeroU64],
+        block_shape: &[NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        let mut header = N5BlockHeader::new_default(block_shape)
+            .map_err(|e| CodecError::Other(format!("N5 block header could not be created: {e}")))?;
+
+        let bytes = if block_shape == shape {
+            bytes
+        } else {
+            super::ShapeRectifier::new_unchecked(bytes, shape, data_type, fill_value, block_shape)
+                .rectify()?
+        };
+        if let ArrayBytes::Variable(strings) = &bytes {
+            // each string is followed by a NUL
+            let num_strings = strings.offsets().len() - 1;
+            let serialized_len = strings.bytes().len() + num_strings;
+            if serialized_len != num_strings {
+                let num_el = u32::try_from(serialized_len).map_err(|_| {
+                    CodecError::Other(format!(
+                        "N5 block of {serialized_len} bytes is too long for a varlength header"
+                    ))
+                })?;
+                header.mode = N5BlockMode::VarLength { num_el };
+            }
+        }
+
+        let body = self
+            .codecs
+            .encode(bytes, block_shape, data_type, fill_value, options)?;
+
+        let mut out = header.to_bytes();
+        out.extend_from_slice(&body);
+        Ok(Cow::Owned(out))
+    }
+
+    /// Decode a block body, detecting its compression if configured to.
+    fn decode_body<'a>(
+        &self,
+        body: &'a [u8],
+        shape: &[NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'a>, CodecError> {
+        if self.detect_compression {
+            let declared = self.codecs.bytes_to_bytes_codecs().first().map(Arc::as_ref);
+            let raw_len = data_type
+                .fixed_size()
+                .map(|size| shape.iter().map(|n| n.get() as usize).product::<usize>() * size);
+            if let Some(detected) =
+                DetectedCompression::detect(body, raw_len).filter(|d| !d.matches(declared))
+            {
+                let codecs = CodecChain::new(
+                    self.codecs.array_to_array_codecs().to_vec(),
+                    self.codecs.array_to_bytes_codec().clone(),
+                    detected.codec().into_iter().collect(),
+                );
+                match codecs.decode(Cow::Borrowed(body), shape, data_type, fill_value, options) {
+                    Ok(decoded) => {
+                        log::warn!(
+                            "N5 block compression detected as {} rather than {}",
+                            detected.name(),
+                            declared
+                                .and_then(|c| c.name(zarrs::plugin::ZarrVersion::V3))
+                                .as_deref()
+                                .unwrap_or("raw"),
+                        );
+                        return Ok(decoded);
+                    }
+                    Err(e) => log::debug!(
+                        "N5 block looks like {} but could not be decoded as such: {e}",
+                        detected.name()
+                    ),
+                }
+            }
+        }
+        // JPEG slices are checked against the block shape, which bytes-to-bytes codecs are not given
+        #[cfg(feature = "jpeg")]
+        if let Some(jpeg) = self
+            .codecs
+            .bytes_to_bytes_codecs()
+            .first()
+            .and_then(|c| c.as_any().downcast_ref::<super::N5JpegCodec>())
+        {
+            let pixels = jpeg.decode_block(body, shape)?;
+            let codecs = CodecChain::new(
+                self.codecs.array_to_array_codecs().to_vec(),
+                self.codecs.array_to_bytes_codec().clone(),
+                Vec::default(),
+            );
+            return Ok(codecs
+                .decode(Cow::Owned(pixels), shape, data_type, fill_value, options)?
+                .into_owned());
+        }
+        self.codecs
+            .decode(Cow::Borrowed(body), shape, data_type, fill_value, options)
+    }
+
+    /// Decode the body of a varlength block of fixed-size elements.
+    ///
+    /// The `num_el` elements fill the block shape in N5 (x-fastest) order, and the rest of the block is the fill value.
+    fn decode_varlength_body(
+        &self,
+        body: &[u8],
+        num_el: u32,
+        shape: &[NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'static>, CodecError> {
+        let num_elements: u64 = shape.iter().map(|n| n.get()).product();
+        if u64::from(num_el) > num_elements {
+            return Err(CodecError::Other(format!(
+                "N5 varlength block has {num_el} elements, more than its shape {shape:?} holds; read it in varlength mode"
+            )));
+        }
+        let Some(element_size) = data_type.fixed_size() else {
+            return Err(CodecError::Other(format!(
+                "N5 varlength blocks of {data_type} cannot be read in default mode"
+            )));
+        };
+        let mut out = ArrayBytes::new_fill_value(data_type, num_elements, fill_value)?
+            .into_fixed()?
+            .into_owned();
+        let Some(num_el) = NonZeroU64::new(num_el.into()) else {
+            return Ok(ArrayBytes::new_flen(out));
+        };
+        // as a column along the first (fastest in N5) dimension, the elements decode in N5 order
+        let mut column_shape = vec![NonZeroU64::MIN; shape.len()];
+        column_shape[0] = num_el;
+        let elements = self
+            .decode_body(body, &column_shape, data_type, fill_value, options)?
+            .into_fixed()?;
+        for (n5_idx, element) in elements.chunks_exact(element_size).enumerate() {
+            // N5 order has the first dimension fastest, C order the last
+            let mut remainder = n5_idx as u64;
+            let mut c_idx = 0;
+            let mut stride = 1;
+            let mut coords = Vec::with_capacity(shape.len());
+            for n in shape {
+                coords.push(remainder % n.get());
+                remainder /= n.get();
+            }
+            for (coord, n) in coords.iter().zip(shape).rev() {
+                c_idx += coord * stride;
+                stride *= n.get();
+            }
+            let start = c_idx as usize * element_size;
+            out[start..start + element_size].copy_from_slice(element);
+        }
+        Ok(ArrayBytes::new_flen(out))
+    }
+
+    /// The shape of the block to be written for a chunk of the given shape.
+    fn block_shape<'a>(&'a self, shape: &'a [NonZeroU64]) -> Result<&'a [NonZeroU64], CodecError> {
+        let Some(block_size) = &self.padded_block_size else {
+            return Ok(shape);
+        };
+        if block_size.len() != shape.len() || std::iter::zip(block_size, shape).any(|(b, s)| s > b)
+        {
+            return Err(CodecError::Other(format!(
+                "chunk shape {shape:?} does not fit in N5 block size {block_size:?}"
+            )));
+        }
+        Ok(block_size)
     }
 }
 
@@ -63,6 +371,27 @@ impl N5DefaultCodec {
 pub struct N5DefaultCodecConfiguration {
     /// Codecs to apply to the block body, i.e. after stripping the N5 block header.
     codecs: Vec<MetadataV3>,
+    /// If present, edge blocks are padded to this shape when encoding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    padded_block_size: Option<Vec<NonZeroU64>>,
+    /// If present, the number of threads the compression may use for each block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    concurrency: Option<NonZeroU32>,
+    /// Whether to detect each block's compression from its magic bytes when decoding.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    detect_compression: bool,
+}
+
+impl N5DefaultCodecConfiguration {
+    /// Metadata for the codecs applied to the block body.
+    pub(crate) fn codecs(&self) -> &[MetadataV3] {
+        &self.codecs
+    }
+
+    /// The number of threads the compression may use for each block, if set.
+    pub(crate) fn concurrency(&self) -> Option<NonZeroU32> {
+        self.concurrency
+    }
 }
 
 impl CodecTraitsV3 for N5DefaultCodec {
@@ -87,7 +416,12 @@ impl CodecTraits for N5DefaultCodec {
         options: &CodecMetadataOptions,
     ) -> Option<zarrs::metadata::Configuration> {
         let metadatas = self.codecs.create_metadatas(options);
-        let config = N5DefaultCodecConfiguration { codecs: metadatas };
+        let config = N5DefaultCodecConfiguration {
+            codecs: metadatas,
+            padded_block_size: self.padded_block_size.clone(),
+            concurrency: self.concurrency,
+            detect_compression: self.detect_compression,
+        };
         let val = serde_json::to_value(config).expect("N5 compression should be serializable");
         let serde_json::Value::Object(map) = val else {
             panic!("N5 compression should serialize to a JSON object");
@@ -96,15 +430,16 @@ impl CodecTraits for N5DefaultCodec {
     }
 
     fn partial_decoder_capability(&self) -> PartialDecoderCapability {
+        let ranged = self.supports_ranged_reads();
         PartialDecoderCapability {
-            partial_read: false,
-            partial_decode: false,
+            partial_read: ranged,
+            partial_decode: ranged,
         }
     }
 
     fn partial_encoder_capability(&self) -> PartialEncoderCapability {
         PartialEncoderCapability {
-            partial_encode: false,
+            partial_encode: true,
         }
     }
 }
@@ -115,10 +450,24 @@ impl ArrayCodecTraits for N5DefaultCodec {
         shape: &[std::num::NonZeroU64],
         data_type: &zarrs::array::DataType,
     ) -> Result<RecommendedConcurrency, CodecError> {
-        self.codecs.recommended_concurrency(shape, data_type)
+        let recommended = self.codecs.recommended_concurrency(shape, data_type)?;
+        match self.concurrency {
+            Some(n) if n.get() as usize > recommended.max() => Ok(RecommendedConcurrency::new(
+                recommended.min()..n.get() as usize,
+            )),
+            _ => Ok(recommended),
+        }
     }
 }
 
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
 impl ArrayToBytesCodecTraits for N5DefaultCodec {
     fn into_dyn(self: Arc<Self>) -> Arc<dyn ArrayToBytesCodecTraits> {
         self
@@ -130,19 +479,30 @@ impl ArrayToBytesCodecTraits for N5DefaultCodec {
         data_type: &zarrs::array::DataType,
         fill_value: &zarrs::array::FillValue,
     ) -> Result<BytesRepresentation, CodecError> {
-        self.codecs
-            .encoded_representation(shape, data_type, fill_value)
+        let block_shape = self.block_shape(shape)?;
+        let header_len = N5BlockHeader::new_default(block_shape)
+            .map_err(|e| CodecError::Other(e.to_string()))?
+            .data_offset() as u64;
+        let body = self
+            .codecs
+            .encoded_representation(block_shape, data_type, fill_value)?;
+        Ok(match body {
+            BytesRepresentation::FixedSize(n) => BytesRepresentation::FixedSize(header_len + n),
+            BytesRepresentation::BoundedSize(n) => BytesRepresentation::BoundedSize(header_len + n),
+            BytesRepresentation::UnboundedSize => BytesRepresentation::UnboundedSize,
+        })
     }
 
     fn encode<'a>(
         &self,
-        _bytes: ArrayBytes<'a>,
-        _shape: &[std::num::NonZeroU64],
-        _data_type: &zarrs::array::DataType,
-        _fill_value: &zarrs::array::FillValue,
-        _options: &CodecOptions,
+        bytes: ArrayBytes<'a>,
+        shape: &[std::num::NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        options: &CodecOptions,
     ) -> Result<ArrayBytesRaw<'a>, CodecError> {
-        Err(CodecError::Other("encoding not supported".into()))
+        let block_shape = self.block_shape(shape)?;
+        self.encode_block(bytes, shape, block_shape, data_type, fill_value, options)
     }
 
     fn decode<'a>(
@@ -156,13 +516,6 @@ impl ArrayToBytesCodecTraits for N5DefaultCodec {
         let header = N5BlockHeader::from_bytes(&bytes)
             .map_err(|e| CodecError::Other(format!("N5 block header could not be parsed: {e}")))?;
 
-        if !matches!(header.mode, N5BlockMode::Default) {
-            return Err(CodecError::Other(format!(
-                "unsupported N5 block mode: {:?}",
-                header.mode
-            )));
-        }
-
         let header_shape: Vec<_> = header
             .shape
             .iter()
@@ -171,13 +524,29 @@ impl ArrayToBytesCodecTraits for N5DefaultCodec {
 
         let payload = &bytes[header.data_offset()..];
 
-        let array_bytes = self.codecs.decode(
-            Cow::Borrowed(payload),
-            &header_shape,
-            data_type,
-            fill_value,
-            options,
-        )?;
+        // each block is decoded according to its own mode
+        let array_bytes = match header.mode {
+            N5BlockMode::Default => {
+                self.decode_body(payload, &header_shape, data_type, fill_value, options)?
+            }
+            // string blocks count their bytes in varlength mode, but still have an element per point
+            N5BlockMode::VarLength { .. } if self.is_string() => {
+                self.decode_body(payload, &header_shape, data_type, fill_value, options)?
+            }
+            N5BlockMode::VarLength { num_el } => self.decode_varlength_body(
+                payload,
+                num_el,
+                &header_shape,
+                data_type,
+                fill_value,
+                options,
+            )?,
+            N5BlockMode::Object { .. } => {
+                return Err(CodecError::Other(
+                    "N5 object blocks must be read in object mode".to_string(),
+                ));
+            }
+        };
 
         super::ShapeRectifier::new_unchecked(
             array_bytes,
@@ -188,4 +557,74 @@ impl ArrayToBytesCodecTraits for N5DefaultCodec {
         )
         .rectify()
     }
+
+    fn partial_decoder(
+        self: Arc<Self>,
+        input_handle: Arc<dyn BytesPartialDecoderTraits>,
+        shape: &[NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        _options: &CodecOptions,
+    ) -> Result<Arc<dyn ArrayPartialDecoderTraits>, CodecError> {
+        Ok(Arc::new(N5DefaultCodecPartial::new(
+            input_handle,
+            self,
+            shape,
+            data_type,
+            fill_value,
+        )))
+    }
+
+    #[cfg(feature = "async")]
+    async fn async_partial_decoder(
+        self: Arc<Self>,
+        input_handle: Arc<dyn AsyncBytesPartialDecoderTraits>,
+        shape: &[NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        _options: &CodecOptions,
+    ) -> Result<Arc<dyn AsyncArrayPartialDecoderTraits>, CodecError> {
+        Ok(Arc::new(N5DefaultCodecPartial::new(
+            input_handle,
+            self,
+            shape,
+            data_type,
+            fill_value,
+        )))
+    }
+
+    fn partial_encoder(
+        self: Arc<Self>,
+        input_output_handle: Arc<dyn BytesPartialEncoderTraits>,
+        shape: &[NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        _options: &CodecOptions,
+    ) -> Result<Arc<dyn ArrayPartialEncoderTraits>, CodecError> {
+        Ok(Arc::new(N5DefaultCodecPartial::new(
+            input_output_handle,
+            self,
+            shape,
+            data_type,
+            fill_value,
+        )))
+    }
+
+    #[cfg(feature = "async")]
+    async fn async_partial_encoder(
+        self: Arc<Self>,
+        input_output_handle: Arc<dyn AsyncBytesPartialEncoderTraits>,
+        shape: &[NonZeroU64],
+        data_type: &zarrs::array::DataType,
+        fill_value: &zarrs::array::FillValue,
+        _options: &CodecOptions,
+    ) -> Result<Arc<dyn AsyncArrayPartialEncoderTraits>, CodecError> {
+        Ok(Arc::new(N5DefaultCodecPartial::new(
+            input_output_handle,
+            self,
+            shape,
+            data_type,
+            fill_value,
+        )))
+    }
 }
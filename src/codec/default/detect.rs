@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use zarrs::array::BytesToBytesCodecTraits;
+use zarrs::array::codec::{
+    BloscCodec, BloscCompressionLevel, BloscCompressor, BloscShuffleMode, Bz2Codec,
+    Bz2CompressionLevel, GzipCodec, ZlibCodec, ZstdCodec,
+};
+
+use crate::codec::N5Lz4Codec;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const LZ4_MAGIC: &[u8] = b"LZ4Block";
+const BLOSC_HEADER_LENGTH: usize = 16;
+const BLOSC_MAX_VERSION_FORMAT: u8 = 2;
+
+/// A compression recognised from the start of an N5 block body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum DetectedCompression {
+    /// Exactly the length of the uncompressed body, and no magic bytes
+    /// (other than those of Blosc and zlib, which are too weak to rule out uncompressed data).
+    Raw,
+    Gzip,
+    Zlib,
+    Bzip2,
+    Zstd,
+    Blosc,
+    Lz4,
+}
+
+impl DetectedCompression {
+    /// Sniff the compression of a block body,
+    /// given its length when uncompressed (if the data type has a fixed size).
+    ///
+    /// Compressions with distinctive magic bytes are checked first, then the uncompressed length,
+    /// then Blosc and zlib, whose headers are short or have no magic bytes.
+    pub(super) fn detect(body: &[u8], raw_len: Option<usize>) -> Option<Self> {
+        let out = if body.starts_with(LZ4_MAGIC) {
+            Self::Lz4
+        } else if body.starts_with(ZSTD_MAGIC) {
+            Self::Zstd
+        } else if body.starts_with(GZIP_MAGIC) {
+            Self::Gzip
+        } else if body.starts_with(BZIP2_MAGIC) && matches!(body.get(3), Some(b'1'..=b'9')) {
+            Self::Bzip2
+        } else if raw_len == Some(body.len()) {
+            // checked before the weaker signatures below, which uncompressed data often matches by chance
+            Self::Raw
+        } else if is_blosc(body) {
+            Self::Blosc
+        } else if is_zlib(body) {
+            Self::Zlib
+        } else {
+            return None;
+        };
+        Some(out)
+    }
+
+    /// Whether the given bytes-to-bytes codec (or lack of one) decodes this compression.
+    pub(super) fn matches(self, codec: Option<&dyn BytesToBytesCodecTraits>) -> bool {
+        let Some(codec) = codec else {
+            return self == Self::Raw;
+        };
+        let any = codec.as_any();
+        match self {
+            Self::Raw => false,
+            Self::Gzip => any.is::<GzipCodec>(),
+            Self::Zlib => any.is::<ZlibCodec>(),
+            Self::Bzip2 => any.is::<Bz2Codec>(),
+            Self::Zstd => any.is::<ZstdCodec>(),
+            Self::Blosc => any.is::<BloscCodec>(),
+            Self::Lz4 => any.is::<N5Lz4Codec>(),
+        }
+    }
+
+    /// A codec which decodes this compression; decoding does not depend on the compression parameters.
+    pub(super) fn codec(self) -> Option<Arc<dyn BytesToBytesCodecTraits>> {
+        let codec: Arc<dyn BytesToBytesCodecTraits> = match self {
+            Self::Raw => return None,
+            Self::Gzip => Arc::new(GzipCodec::new(6).expect("valid gzip level")),
+            Self::Zlib => Arc::new(ZlibCodec::new(6u32.try_into().expect("valid zlib level"))),
+            Self::Bzip2 => Arc::new(Bz2Codec::new(
+                Bz2CompressionLevel::new(9).expect("valid bz2 level"),
+            )),
+            Self::Zstd => Arc::new(ZstdCodec::new(3, false)),
+            Self::Blosc => Arc::new(
+                BloscCodec::new(
+                    BloscCompressor::BloscLZ,
+                    BloscCompressionLevel::try_from(5).expect("valid blosc level"),
+                    None,
+                    BloscShuffleMode::NoShuffle,
+                    None,
+                )
+                .expect("valid blosc configuration"),
+            ),
+            Self::Lz4 => Arc::new(N5Lz4Codec::new(1 << 16).expect("valid LZ4 block size")),
+        };
+        Some(codec)
+    }
+
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Gzip => "gzip",
+            Self::Zlib => "zlib",
+            Self::Bzip2 => "bzip2",
+            Self::Zstd => "zstd",
+            Self::Blosc => "blosc",
+            Self::Lz4 => "lz4",
+        }
+    }
+}
+
+/// Blosc has no magic bytes, but its header records the compressed length.
+fn is_blosc(body: &[u8]) -> bool {
+    if body.len() < BLOSC_HEADER_LENGTH || !(1..=BLOSC_MAX_VERSION_FORMAT).contains(&body[0]) {
+        return false;
+    }
+    let cbytes = u32::from_le_bytes(body[12..16].try_into().unwrap());
+    cbytes as usize == body.len()
+}
+
+/// A zlib header is a deflate method byte and a flag byte, which together are a multiple of 31.
+fn is_zlib(body: &[u8]) -> bool {
+    let [cmf, flg, ..] = body else {
+        return false;
+    };
+    cmf & 0x0f == 8 && cmf >> 4 <= 7 && u16::from_be_bytes([*cmf, *flg]) % 31 == 0
+}
@@ -0,0 +1,595 @@
+use std::borrow::Cow;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use zarrs::array::codec::api::{
+    ArrayBytes, ArrayBytesRaw, ArrayPartialDecoderTraits, ArrayPartialEncoderTraits,
+    ArrayToBytesCodecTraits, BytesPartialDecoderTraits, BytesPartialEncoderTraits, CodecError,
+    CodecOptions, update_array_bytes,
+};
+#[cfg(feature = "async")]
+use zarrs::array::codec::api::{
+    AsyncArrayPartialDecoderTraits, AsyncArrayPartialEncoderTraits, AsyncBytesPartialDecoderTraits,
+    AsyncBytesPartialEncoderTraits,
+};
+use zarrs::array::{ArraySubset, ArraySubsetTraits, DataType, FillValue, Indexer};
+use zarrs::storage::StorageError;
+use zarrs::storage::byte_range::ByteRange;
+
+use super::N5DefaultCodec;
+use crate::{N5BlockHeader, N5BlockMode};
+
+/// Partial decoder and encoder for the [N5DefaultCodec].
+///
+/// When decoding a subset of an uncompressed block, only the header and the byte ranges of the subset are read
+/// (see [N5DefaultCodec::supports_ranged_reads]).
+/// Other blocks are read and decoded whole.
+///
+/// Blocks which can be read by byte range and whose header covers the whole chunk are updated in place,
+/// reading only the header and writing only the byte ranges of the updated region.
+/// Other blocks are read, updated and re-encoded with their existing header shape,
+/// so truncated blocks stay truncated and padded blocks stay padded.
+pub(crate) struct N5DefaultCodecPartial<T: ?Sized> {
+    input_output_handle: Arc<T>,
+    codec: Arc<N5DefaultCodec>,
+    shape: Vec<NonZeroU64>,
+    data_type: DataType,
+    fill_value: FillValue,
+}
+
+type OffsetBytes = Vec<(u64, ArrayBytesRaw<'static>)>;
+
+/// The part of an array subset which lies within a block, and the byte ranges of the block holding it.
+struct RangedRead {
+    /// Byte ranges of the block body, in C order over the reversed (N5) shape.
+    byte_ranges: Vec<ByteRange>,
+    /// The shape of the part of the subset within the block, which starts at the subset's origin.
+    read_shape: Vec<u64>,
+    subset_shape: Vec<u64>,
+}
+
+impl<T: ?Sized> N5DefaultCodecPartial<T> {
+    pub(crate) fn new(
+        input_output_handle: Arc<T>,
+        codec: Arc<N5DefaultCodec>,
+        shape: &[NonZeroU64],
+        data_type: &DataType,
+        fill_value: &FillValue,
+    ) -> Self {
+        Self {
+            input_output_handle,
+            codec,
+            shape: shape.to_vec(),
+            data_type: data_type.clone(),
+            fill_value: fill_value.clone(),
+        }
+    }
+
+    fn shape_u64(&self) -> Vec<u64> {
+        self.shape.iter().map(|n| n.get()).collect()
+    }
+
+    /// Decode the whole chunk, or fill it if the block is missing.
+    fn decode_chunk(
+        &self,
+        encoded: Option<ArrayBytesRaw<'_>>,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'static>, CodecError> {
+        match encoded {
+            Some(encoded) => Ok(self
+                .codec
+                .decode(
+                    encoded,
+                    &self.shape,
+                    &self.data_type,
+                    &self.fill_value,
+                    options,
+                )?
+                .into_owned()),
+            None => Ok(ArrayBytes::new_fill_value(
+                &self.data_type,
+                self.shape.iter().map(|n| n.get()).product(),
+                &self.fill_value,
+            )?),
+        }
+    }
+
+    /// The byte range holding the header of a default-mode block of this dimensionality.
+    fn header_range(&self) -> ByteRange {
+        let header = N5BlockHeader {
+            mode: N5BlockMode::Default,
+            shape: vec![0; self.shape.len()],
+        };
+        ByteRange::FromStart(0, Some(header.data_offset() as u64))
+    }
+
+    /// Whether blocks may be read by byte range rather than decoded whole.
+    fn reads_ranges(&self) -> bool {
+        self.codec.supports_ranged_reads() && self.data_type.fixed_size().is_some()
+    }
+
+    /// Plan which byte ranges of the block body to read for the indexed subset, given the block header.
+    ///
+    /// Returns [None] if the block must be decoded whole,
+    /// i.e. it is not a default-mode block of the expected dimensionality.
+    fn ranged_read(
+        &self,
+        header: &[u8],
+        subset: &dyn ArraySubsetTraits,
+    ) -> Result<Option<RangedRead>, CodecError> {
+        let Ok(header) = N5BlockHeader::from_bytes(header) else {
+            return Ok(None);
+        };
+        let Some(data_type_size) = self.data_type.fixed_size() else {
+            return Ok(None);
+        };
+        if !matches!(header.mode, N5BlockMode::Default) || header.shape.len() != self.shape.len() {
+            return Ok(None);
+        }
+        // truncated blocks may not cover the whole subset, and the rest is filled
+        let (start, shape) = (subset.start(), subset.shape());
+        let read_shape: Vec<u64> = std::iter::zip(start.iter(), shape.iter())
+            .zip(&header.shape)
+            .map(|((start, len), block)| {
+                (start + len).min(u64::from(*block)).saturating_sub(*start)
+            })
+            .collect();
+        let mut byte_ranges = Vec::default();
+        if read_shape.iter().all(|n| *n > 0) {
+            // the body is in C order over the reversed header shape
+            let reversed = ArraySubset::new_with_start_shape(
+                start.iter().rev().copied().collect(),
+                read_shape.iter().rev().copied().collect(),
+            )
+            .map_err(|e| CodecError::Other(e.to_string()))?;
+            let reversed_block_shape: Vec<u64> =
+                header.shape.iter().rev().map(|n| u64::from(*n)).collect();
+            let data_offset = header.data_offset() as u64;
+            byte_ranges = reversed
+                .iter_contiguous_byte_ranges(&reversed_block_shape, data_type_size)?
+                .map(|range| {
+                    ByteRange::FromStart(data_offset + range.start, Some(range.end - range.start))
+                })
+                .collect();
+        }
+        Ok(Some(RangedRead {
+            byte_ranges,
+            read_shape,
+            subset_shape: shape.into_owned(),
+        }))
+    }
+
+    /// Decode the byte ranges read for a [RangedRead] (or [None] if the block is missing).
+    fn decode_ranges(
+        &self,
+        read: &RangedRead,
+        ranges: Option<Vec<ArrayBytesRaw<'_>>>,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'static>, CodecError> {
+        let (Some(ranges), Some(read_shape)) = (ranges, nonzero_shape(&read.read_shape)) else {
+            return Ok(ArrayBytes::new_fill_value(
+                &self.data_type,
+                read.subset_shape.iter().product(),
+                &self.fill_value,
+            )?);
+        };
+        let decoded = self.codec.codecs.decode(
+            Cow::Owned(ranges.concat()),
+            &read_shape,
+            &self.data_type,
+            &self.fill_value,
+            options,
+        )?;
+        if read.read_shape == read.subset_shape {
+            return Ok(decoded.into_owned());
+        }
+        let Some(subset_shape) = nonzero_shape(&read.subset_shape) else {
+            unreachable!("the subset is at least as large as the non-empty read")
+        };
+        crate::codec::ShapeRectifier::new_unchecked(
+            decoded,
+            &read_shape,
+            &self.data_type,
+            &self.fill_value,
+            &subset_shape,
+        )
+        .rectify()
+    }
+
+    /// Extract the indexed elements from the whole encoded chunk.
+    fn extract(
+        &self,
+        encoded: Option<ArrayBytesRaw<'_>>,
+        indexer: &dyn Indexer,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'static>, CodecError> {
+        if encoded.is_none() {
+            return Ok(ArrayBytes::new_fill_value(
+                &self.data_type,
+                indexer.len(),
+                &self.fill_value,
+            )?);
+        }
+        let decoded = self.decode_chunk(encoded, options)?;
+        Ok(decoded
+            .extract_array_subset(indexer, &self.shape_u64(), &self.data_type)?
+            .into_owned())
+    }
+
+    /// Whether blocks may be updated in place by the given update, judging by everything but the block header.
+    ///
+    /// This needs blocks which can be read by byte range (see [Self::reads_ranges]),
+    /// and an update which does not only contain the fill value.
+    /// Otherwise, the update might leave a block of only the fill value,
+    /// which should be erased rather than written (unless empty chunks are stored).
+    fn may_update_in_place(&self, bytes: &ArrayBytes<'_>, options: &CodecOptions) -> bool {
+        self.reads_ranges()
+            && (options.store_empty_chunks() || !bytes.is_fill_value(&self.fill_value))
+    }
+
+    /// If the block with the given header can be updated in place,
+    /// return the length the block must have, and the offsets and bytes to write.
+    ///
+    /// This is the case for default-mode blocks whose header shape covers the chunk,
+    /// when the update is a contiguous subset (see also [Self::may_update_in_place]).
+    fn in_place_update(
+        &self,
+        header: &[u8],
+        indexer: &dyn Indexer,
+        bytes: &ArrayBytes<'_>,
+        options: &CodecOptions,
+    ) -> Result<Option<(u64, OffsetBytes)>, CodecError> {
+        let Ok(header) = N5BlockHeader::from_bytes(header) else {
+            return Ok(None);
+        };
+        if !matches!(header.mode, N5BlockMode::Default)
+            || header.shape.len() != self.shape.len()
+            || std::iter::zip(&header.shape, &self.shape).any(|(h, s)| u64::from(*h) < s.get())
+        {
+            return Ok(None);
+        }
+        let (Some(data_type_size), Some(subset)) =
+            (self.data_type.fixed_size(), indexer.as_array_subset())
+        else {
+            return Ok(None);
+        };
+        let body_len: usize =
+            header.shape.iter().map(|n| *n as usize).product::<usize>() * data_type_size;
+        let block_len = (header.data_offset() + body_len) as u64;
+        let Ok(subset_shape) = subset
+            .shape()
+            .iter()
+            .map(|n| NonZeroU64::try_from(*n))
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            // empty subset
+            return Ok(Some((block_len, Vec::default())));
+        };
+
+        // the body is big-endian and in C order over the reversed header shape,
+        // so encode the update with the same codecs and write it into the reversed subset
+        let body = self.codec.codecs.encode(
+            bytes.clone(),
+            &subset_shape,
+            &self.data_type,
+            &self.fill_value,
+            options,
+        )?;
+        let reversed = ArraySubset::new_with_start_shape(
+            subset.start().iter().rev().copied().collect(),
+            subset.shape().iter().rev().copied().collect(),
+        )
+        .map_err(|e| CodecError::Other(e.to_string()))?;
+        let reversed_block_shape: Vec<u64> =
+            header.shape.iter().rev().map(|n| u64::from(*n)).collect();
+        let data_offset = header.data_offset() as u64;
+
+        let mut consumed = 0;
+        let updates = reversed
+            .iter_contiguous_byte_ranges(&reversed_block_shape, data_type_size)?
+            .map(|range| {
+                let len = (range.end - range.start) as usize;
+                let value = body[consumed..consumed + len].to_vec();
+                consumed += len;
+                (data_offset + range.start, Cow::Owned(value))
+            })
+            .collect();
+        Ok(Some((block_len, updates)))
+    }
+
+    /// Update the whole chunk and re-encode it, keeping the existing header shape if it covers the chunk.
+    ///
+    /// Returns [None] if the updated chunk should not be stored.
+    fn updated_block(
+        &self,
+        encoded: Option<ArrayBytesRaw<'_>>,
+        indexer: &dyn Indexer,
+        bytes: &ArrayBytes<'_>,
+        options: &CodecOptions,
+    ) -> Result<Option<ArrayBytesRaw<'static>>, CodecError> {
+        bytes.validate(indexer.len(), &self.data_type)?;
+
+        let existing_shape = match &encoded {
+            Some(b) => Some(N5BlockHeader::from_bytes(b).map_err(|e| {
+                CodecError::Other(format!("N5 block header could not be parsed: {e}"))
+            })?),
+            None => None,
+        }
+        .and_then(|h| {
+            h.shape
+                .iter()
+                .map(|n| NonZeroU64::new(u64::from(*n)))
+                .collect::<Option<Vec<_>>>()
+        })
+        .filter(|h| {
+            h.len() == self.shape.len() && std::iter::zip(h, &self.shape).all(|(h, s)| h >= s)
+        });
+
+        let decoded = self.decode_chunk(encoded, options)?;
+        let updated = update_array_bytes(
+            decoded,
+            &self.shape_u64(),
+            indexer,
+            bytes,
+            self.data_type.size(),
+        )?;
+        if !options.store_empty_chunks() && updated.is_fill_value(&self.fill_value) {
+            return Ok(None);
+        }
+
+        let block_shape = match &existing_shape {
+            Some(s) => s.as_slice(),
+            None => self.codec.block_shape(&self.shape)?,
+        };
+        Ok(Some(
+            self.codec
+                .encode_block(
+                    updated,
+                    &self.shape,
+                    block_shape,
+                    &self.data_type,
+                    &self.fill_value,
+                    options,
+                )?
+                .into_owned()
+                .into(),
+        ))
+    }
+}
+
+impl<T: ?Sized> ArrayPartialDecoderTraits for N5DefaultCodecPartial<T>
+where
+    T: BytesPartialDecoderTraits,
+{
+    fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    fn exists(&self) -> Result<bool, StorageError> {
+        self.input_output_handle.exists()
+    }
+
+    fn size_held(&self) -> usize {
+        self.input_output_handle.size_held()
+    }
+
+    fn partial_decode(
+        &self,
+        indexer: &dyn Indexer,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'_>, CodecError> {
+        if let Some(subset) = indexer.as_array_subset().filter(|_| self.reads_ranges()) {
+            let header = self
+                .input_output_handle
+                .partial_decode(self.header_range(), options)?;
+            let Some(header) = header else {
+                return self.extract(None, indexer, options);
+            };
+            if let Some(read) = self.ranged_read(&header, subset)? {
+                let ranges = self
+                    .input_output_handle
+                    .partial_decode_many(Box::new(read.byte_ranges.iter().copied()), options)?;
+                return self.decode_ranges(&read, ranges, options);
+            }
+        }
+        let encoded = self.input_output_handle.decode(options)?;
+        self.extract(encoded, indexer, options)
+    }
+
+    fn supports_partial_decode(&self) -> bool {
+        self.reads_ranges()
+    }
+}
+
+impl<T: ?Sized> ArrayPartialEncoderTraits for N5DefaultCodecPartial<T>
+where
+    T: BytesPartialEncoderTraits,
+{
+    fn into_dyn_decoder(self: Arc<Self>) -> Arc<dyn ArrayPartialDecoderTraits> {
+        self
+    }
+
+    fn erase(&self) -> Result<(), CodecError> {
+        self.input_output_handle.erase()
+    }
+
+    fn partial_encode(
+        &self,
+        indexer: &dyn Indexer,
+        bytes: &ArrayBytes<'_>,
+        options: &CodecOptions,
+    ) -> Result<(), CodecError> {
+        let encoded = if self.may_update_in_place(bytes, options) {
+            match self
+                .input_output_handle
+                .partial_decode(self.header_range(), options)
+            {
+                Ok(None) => None,
+                Ok(Some(header)) => {
+                    if let Some((block_len, updates)) =
+                        self.in_place_update(&header, indexer, bytes, options)?
+                        && is_last_byte(
+                            self.input_output_handle
+                                .partial_decode(last_byte_range(block_len), options),
+                        )
+                    {
+                        return self
+                            .input_output_handle
+                            .partial_encode_many(Box::new(updates.into_iter()), options);
+                    }
+                    self.input_output_handle.decode(options)?
+                }
+                // the block is shorter than a default-mode header
+                Err(_) => self.input_output_handle.decode(options)?,
+            }
+        } else {
+            self.input_output_handle.decode(options)?
+        };
+
+        let block = self.updated_block(encoded, indexer, bytes, options)?;
+        self.input_output_handle.erase()?;
+        match block {
+            Some(block) => self.input_output_handle.partial_encode(0, block, options),
+            None => Ok(()),
+        }
+    }
+
+    fn supports_partial_encode(&self) -> bool {
+        self.input_output_handle.supports_partial_encode()
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl<T: ?Sized> AsyncArrayPartialDecoderTraits for N5DefaultCodecPartial<T>
+where
+    T: AsyncBytesPartialDecoderTraits,
+{
+    fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    async fn exists(&self) -> Result<bool, StorageError> {
+        self.input_output_handle.exists().await
+    }
+
+    fn size_held(&self) -> usize {
+        self.input_output_handle.size_held()
+    }
+
+    async fn partial_decode<'a>(
+        &'a self,
+        indexer: &dyn Indexer,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'a>, CodecError> {
+        if let Some(subset) = indexer.as_array_subset().filter(|_| self.reads_ranges()) {
+            let header = self
+                .input_output_handle
+                .partial_decode(self.header_range(), options)
+                .await?;
+            let Some(header) = header else {
+                return self.extract(None, indexer, options);
+            };
+            if let Some(read) = self.ranged_read(&header, subset)? {
+                let ranges = self
+                    .input_output_handle
+                    .partial_decode_many(Box::new(read.byte_ranges.iter().copied()), options)
+                    .await?;
+                return self.decode_ranges(&read, ranges, options);
+            }
+        }
+        let encoded = self.input_output_handle.decode(options).await?;
+        self.extract(encoded, indexer, options)
+    }
+
+    fn supports_partial_decode(&self) -> bool {
+        self.reads_ranges()
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl<T: ?Sized> AsyncArrayPartialEncoderTraits for N5DefaultCodecPartial<T>
+where
+    T: AsyncBytesPartialEncoderTraits,
+{
+    fn into_dyn_decoder(self: Arc<Self>) -> Arc<dyn AsyncArrayPartialDecoderTraits> {
+        self
+    }
+
+    async fn erase(&self) -> Result<(), CodecError> {
+        self.input_output_handle.erase().await
+    }
+
+    async fn partial_encode(
+        &self,
+        indexer: &dyn Indexer,
+        bytes: &ArrayBytes<'_>,
+        options: &CodecOptions,
+    ) -> Result<(), CodecError> {
+        let encoded = if self.may_update_in_place(bytes, options) {
+            match self
+                .input_output_handle
+                .partial_decode(self.header_range(), options)
+                .await
+            {
+                Ok(None) => None,
+                Ok(Some(header)) => {
+                    if let Some((block_len, updates)) =
+                        self.in_place_update(&header, indexer, bytes, options)?
+                        && is_last_byte(
+                            self.input_output_handle
+                                .partial_decode(last_byte_range(block_len), options)
+                                .await,
+                        )
+                    {
+                        return self
+                            .input_output_handle
+                            .partial_encode_many(Box::new(updates.into_iter()), options)
+                            .await;
+                    }
+                    self.input_output_handle.decode(options).await?
+                }
+                // the block is shorter than a default-mode header
+                Err(_) => self.input_output_handle.decode(options).await?,
+            }
+        } else {
+            self.input_output_handle.decode(options).await?
+        };
+
+        let block = self.updated_block(encoded, indexer, bytes, options)?;
+        self.input_output_handle.erase().await?;
+        match block {
+            Some(block) => {
+                self.input_output_handle
+                    .partial_encode(0, block, options)
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn supports_partial_encode(&self) -> bool {
+        self.input_output_handle.supports_partial_encode()
+    }
+}
+
+/// The shape as [NonZeroU64]s, or [None] if it is empty.
+fn nonzero_shape(shape: &[u64]) -> Option<Vec<NonZeroU64>> {
+    shape.iter().map(|n| NonZeroU64::new(*n)).collect()
+}
+
+/// The last byte of a block of the given length.
+///
+/// Codecs cannot ask the store for the size of a block, but reading this range fails if the block is shorter,
+/// so it is read before updating a block in place to ensure the update does not extend the block.
+fn last_byte_range(block_len: u64) -> ByteRange {
+    ByteRange::FromStart(block_len.saturating_sub(1), Some(1))
+}
+
+/// Whether reading [last_byte_range] found that the block is long enough.
+fn is_last_byte(read: Result<Option<ArrayBytesRaw<'_>>, CodecError>) -> bool {
+    matches!(read, Ok(Some(b)) if b.len() == 1)
+}
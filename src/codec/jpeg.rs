@@ -0,0 +1,213 @@
+use std::borrow::Cow;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use jpeg_decoder::{Decoder, PixelFormat};
+use serde::{Deserialize, Serialize};
+use zarrs::array::codec::api::{
+    ArrayBytesRaw, BytesRepresentation, BytesToBytesCodecTraits, Codec, CodecError,
+    CodecMetadataOptions, CodecOptions, CodecPluginV3, CodecTraits, CodecTraitsV3,
+    PartialDecoderCapability, PartialEncoderCapability, RecommendedConcurrency,
+};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::PluginCreateError;
+
+zarrs::plugin::impl_extension_aliases!(N5JpegCodec, v3: "n5_jpeg", ["zarrs.n5_jpeg"]);
+inventory::submit! {
+    CodecPluginV3::new::<N5JpegCodec>()
+}
+
+const MAX_QUALITY: u8 = 100;
+
+/// JPEG compression of `uint8` blocks, as written by [n5-jpeg](https://github.com/saalfeldlab/n5-jpeg).
+///
+/// Each block is stored as a sequence of 8-bit greyscale JPEG images, one per z-slice:
+/// each image is the block's first dimension wide and its second dimension high,
+/// and slices along the remaining dimensions follow each other in order.
+/// Concatenating the decoded pixels therefore gives the block's bytes in N5 order.
+/// Within an [crate::N5DefaultCodec], the size of each image is checked against the block shape.
+///
+/// This codec is decode-only.
+#[derive(Debug, Clone)]
+pub struct N5JpegCodec {
+    quality: u8,
+}
+
+impl N5JpegCodec {
+    /// Create a codec with the given quality, which must be in the range 0..=100.
+    pub fn new(quality: u8) -> crate::Result<Self> {
+        if quality > MAX_QUALITY {
+            return Err(crate::Error::general(format!(
+                "invalid JPEG quality {quality}, must be in the range 0..={MAX_QUALITY}"
+            )));
+        }
+        Ok(Self { quality })
+    }
+
+    pub fn new_with_configuration(
+        configuration: &N5JpegCodecConfiguration,
+    ) -> Result<Self, PluginCreateError> {
+        Self::new(configuration.quality).map_err(|e| PluginCreateError::Other(e.to_string()))
+    }
+
+    pub fn quality(&self) -> u8 {
+        self.quality
+    }
+
+    /// Decode a block body of the given (N5-ordered) shape, checking each slice against the block's first two dimensions.
+    pub(crate) fn decode_block(
+        &self,
+        encoded: &[u8],
+        shape: &[NonZeroU64],
+    ) -> Result<Vec<u8>, CodecError> {
+        let extent = |i: usize| shape.get(i).map_or(1, |n| n.get());
+        let num_elements: u64 = shape.iter().map(|n| n.get()).product();
+        let out = decode_slices(encoded, Some((extent(0), extent(1))), num_elements as usize)?;
+        if out.len() as u64 != num_elements {
+            return Err(corrupted(format!(
+                "expected {num_elements} pixels, got {}",
+                out.len()
+            )));
+        }
+        Ok(out)
+    }
+}
+
+fn corrupted(msg: impl std::fmt::Display) -> CodecError {
+    CodecError::Other(format!("corrupted N5 JPEG block: {msg}"))
+}
+
+/// Decode a sequence of concatenated greyscale JPEG images into their concatenated pixels.
+///
+/// If given, each image must have the expected width and height.
+fn decode_slices(
+    mut encoded: &[u8],
+    slice_shape: Option<(u64, u64)>,
+    capacity: usize,
+) -> Result<Vec<u8>, CodecError> {
+    let mut out = Vec::with_capacity(capacity);
+    while !encoded.is_empty() {
+        // the decoder consumes the reader up to the end of the image, leaving the next one
+        let mut decoder = Decoder::new(&mut encoded);
+        let pixels = decoder.decode().map_err(corrupted)?;
+        let info = decoder
+            .info()
+            .ok_or_else(|| corrupted("missing image info"))?;
+        if info.pixel_format != PixelFormat::L8 {
+            return Err(corrupted(format!(
+                "expected 8-bit greyscale slices, got {:?}",
+                info.pixel_format
+            )));
+        }
+        if let Some((width, height)) = slice_shape
+            && (u64::from(info.width), u64::from(info.height)) != (width, height)
+        {
+            return Err(corrupted(format!(
+                "expected {width}x{height} slices, got {}x{}",
+                info.width, info.height
+            )));
+        }
+        out.extend_from_slice(&pixels);
+    }
+    Ok(out)
+}
+
+/// Configuration for [N5JpegCodec].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct N5JpegCodecConfiguration {
+    /// Compression quality in the range 0..=100.
+    pub quality: u8,
+}
+
+impl CodecTraitsV3 for N5JpegCodec {
+    fn create(metadata: &MetadataV3) -> Result<Codec, PluginCreateError>
+    where
+        Self: Sized,
+    {
+        let configuration = metadata.to_typed_configuration()?;
+        let codec = Arc::new(N5JpegCodec::new_with_configuration(&configuration)?);
+        Ok(Codec::BytesToBytes(codec))
+    }
+}
+
+impl CodecTraits for N5JpegCodec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn configuration(
+        &self,
+        _version: zarrs::plugin::ZarrVersion,
+        _options: &CodecMetadataOptions,
+    ) -> Option<zarrs::metadata::Configuration> {
+        let config = N5JpegCodecConfiguration {
+            quality: self.quality,
+        };
+        let val = serde_json::to_value(config).expect("JPEG configuration should be serializable");
+        let serde_json::Value::Object(map) = val else {
+            panic!("JPEG configuration should serialize to a JSON object");
+        };
+        Some(map.into())
+    }
+
+    fn partial_decoder_capability(&self) -> PartialDecoderCapability {
+        PartialDecoderCapability {
+            partial_read: false,
+            partial_decode: false,
+        }
+    }
+
+    fn partial_encoder_capability(&self) -> PartialEncoderCapability {
+        PartialEncoderCapability {
+            partial_encode: false,
+        }
+    }
+}
+
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+impl BytesToBytesCodecTraits for N5JpegCodec {
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn BytesToBytesCodecTraits> {
+        self
+    }
+
+    fn recommended_concurrency(
+        &self,
+        _decoded_representation: &BytesRepresentation,
+    ) -> Result<RecommendedConcurrency, CodecError> {
+        Ok(RecommendedConcurrency::new_maximum(1))
+    }
+
+    fn encode<'a>(
+        &self,
+        _decoded_value: ArrayBytesRaw<'a>,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        Err(CodecError::Other(
+            "N5 JPEG compression is decode-only".to_string(),
+        ))
+    }
+
+    fn decode<'a>(
+        &self,
+        encoded_value: ArrayBytesRaw<'a>,
+        decoded_representation: &BytesRepresentation,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        let capacity = decoded_representation.size().unwrap_or_default() as usize;
+        Ok(Cow::Owned(decode_slices(&encoded_value, None, capacity)?))
+    }
+
+    fn encoded_representation(
+        &self,
+        _decoded_representation: &BytesRepresentation,
+    ) -> BytesRepresentation {
+        BytesRepresentation::UnboundedSize
+    }
+}
@@ -0,0 +1,265 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use zarrs::array::codec::api::{
+    ArrayBytesRaw, BytesRepresentation, BytesToBytesCodecTraits, Codec, CodecError,
+    CodecMetadataOptions, CodecOptions, CodecPluginV3, CodecTraits, CodecTraitsV3,
+    PartialDecoderCapability, PartialEncoderCapability, RecommendedConcurrency,
+};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::PluginCreateError;
+
+zarrs::plugin::impl_extension_aliases!(N5Lz4Codec, v3: "n5_lz4", ["zarrs.n5_lz4"]);
+inventory::submit! {
+    CodecPluginV3::new::<N5Lz4Codec>()
+}
+
+const MAGIC: &[u8; 8] = b"LZ4Block";
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 4 + 4 + 4;
+const COMPRESSION_METHOD_RAW: u8 = 0x10;
+const COMPRESSION_METHOD_LZ4: u8 = 0x20;
+const COMPRESSION_LEVEL_BASE: u32 = 10;
+const MIN_BLOCK_SIZE: u32 = 64;
+const MAX_BLOCK_SIZE: u32 = 1 << (COMPRESSION_LEVEL_BASE + 0x0F);
+const CHECKSUM_SEED: u32 = 0x9747b28c;
+
+/// LZ4 compression as written by n5-java, using the framing of lz4-java's `LZ4BlockOutputStream`.
+///
+/// The input is split into blocks of at most `block_size` bytes (N5's LZ4 `level`).
+/// Each block is preceded by a 21-byte header:
+/// the magic `LZ4Block`, a token containing the compression method and block size exponent,
+/// the little-endian compressed and uncompressed lengths,
+/// and an xxhash32 checksum of the uncompressed bytes (truncated to 28 bits, as lz4-java does).
+/// Blocks which do not shrink are stored uncompressed.
+/// The stream ends with an empty block.
+#[derive(Debug, Clone)]
+pub struct N5Lz4Codec {
+    block_size: u32,
+    compression_level: u8,
+}
+
+impl N5Lz4Codec {
+    /// Create a codec with the given block size,
+    /// which must be in the range 64 to 32MiB.
+    pub fn new(block_size: u32) -> crate::Result<Self> {
+        if !(MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&block_size) {
+            return Err(crate::Error::general(format!(
+                "invalid LZ4 block size {block_size}, must be in the range {MIN_BLOCK_SIZE}..={MAX_BLOCK_SIZE}"
+            )));
+        }
+        // ceil(log2(block_size))
+        let exponent = u32::BITS - (block_size - 1).leading_zeros();
+        let compression_level = exponent.saturating_sub(COMPRESSION_LEVEL_BASE) as u8;
+        Ok(Self {
+            block_size,
+            compression_level,
+        })
+    }
+
+    pub fn new_with_configuration(
+        configuration: &N5Lz4CodecConfiguration,
+    ) -> Result<Self, PluginCreateError> {
+        Self::new(configuration.block_size).map_err(|e| PluginCreateError::Other(e.to_string()))
+    }
+
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    fn encode_block(&self, block: &[u8], out: &mut Vec<u8>) {
+        let compressed = lz4_flex::block::compress(block);
+        let (method, body) = if compressed.len() >= block.len() {
+            (COMPRESSION_METHOD_RAW, block)
+        } else {
+            (COMPRESSION_METHOD_LZ4, compressed.as_slice())
+        };
+        out.extend_from_slice(MAGIC);
+        out.push(method | self.compression_level);
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        out.extend_from_slice(&checksum(block).to_le_bytes());
+        out.extend_from_slice(body);
+    }
+
+    fn encode_terminator(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(MAGIC);
+        out.push(COMPRESSION_METHOD_RAW | self.compression_level);
+        out.extend_from_slice(&[0; 12]);
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    twox_hash::XxHash32::oneshot(CHECKSUM_SEED, bytes) & 0x0FFF_FFFF
+}
+
+fn corrupted(msg: impl std::fmt::Display) -> CodecError {
+    CodecError::Other(format!("corrupted LZ4 block stream: {msg}"))
+}
+
+/// Decode an `LZ4BlockOutputStream` stream, regardless of the block size it was written with.
+fn decode_stream(mut encoded: &[u8]) -> Result<Vec<u8>, CodecError> {
+    let mut out = Vec::default();
+    // lz4-java treats a clean end of stream as the end of the data, even without an empty block
+    while !encoded.is_empty() {
+        let Some((header, rest)) = encoded.split_first_chunk::<HEADER_LENGTH>() else {
+            return Err(corrupted("truncated block header"));
+        };
+        if !header.starts_with(MAGIC) {
+            return Err(corrupted("bad magic"));
+        }
+        let token = header[MAGIC.len()];
+        let method = token & 0xF0;
+        let max_len = 1usize << (COMPRESSION_LEVEL_BASE + u32::from(token & 0x0F));
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap()) as usize
+        };
+        let compressed_len = read_u32(MAGIC.len() + 1);
+        let original_len = read_u32(MAGIC.len() + 5);
+        let check = read_u32(MAGIC.len() + 9) as u32;
+
+        if original_len > max_len
+            || (original_len == 0) != (compressed_len == 0)
+            || (method == COMPRESSION_METHOD_RAW && original_len != compressed_len)
+        {
+            return Err(corrupted("invalid block lengths"));
+        }
+        if original_len == 0 {
+            if check != 0 {
+                return Err(corrupted("non-zero checksum of empty block"));
+            }
+            break;
+        }
+        let Some((body, rest)) = rest.split_at_checked(compressed_len) else {
+            return Err(corrupted("truncated block"));
+        };
+
+        let start = out.len();
+        match method {
+            COMPRESSION_METHOD_RAW => out.extend_from_slice(body),
+            COMPRESSION_METHOD_LZ4 => {
+                out.resize(start + original_len, 0);
+                let n =
+                    lz4_flex::block::decompress_into(body, &mut out[start..]).map_err(corrupted)?;
+                if n != original_len {
+                    return Err(corrupted("block decompressed to the wrong length"));
+                }
+            }
+            m => return Err(corrupted(format!("unknown compression method {m:#x}"))),
+        }
+        if checksum(&out[start..]) != check {
+            return Err(corrupted("checksum mismatch"));
+        }
+        encoded = rest;
+    }
+    Ok(out)
+}
+
+/// Configuration for [N5Lz4Codec].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct N5Lz4CodecConfiguration {
+    /// Maximum number of uncompressed bytes in each LZ4 block; N5's LZ4 `level`.
+    pub block_size: u32,
+}
+
+impl CodecTraitsV3 for N5Lz4Codec {
+    fn create(metadata: &MetadataV3) -> Result<Codec, PluginCreateError>
+    where
+        Self: Sized,
+    {
+        let configuration = metadata.to_typed_configuration()?;
+        let codec = Arc::new(N5Lz4Codec::new_with_configuration(&configuration)?);
+        Ok(Codec::BytesToBytes(codec))
+    }
+}
+
+impl CodecTraits for N5Lz4Codec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn configuration(
+        &self,
+        _version: zarrs::plugin::ZarrVersion,
+        _options: &CodecMetadataOptions,
+    ) -> Option<zarrs::metadata::Configuration> {
+        let config = N5Lz4CodecConfiguration {
+            block_size: self.block_size,
+        };
+        let val = serde_json::to_value(config).expect("LZ4 configuration should be serializable");
+        let serde_json::Value::Object(map) = val else {
+            panic!("LZ4 configuration should serialize to a JSON object");
+        };
+        Some(map.into())
+    }
+
+    fn partial_decoder_capability(&self) -> PartialDecoderCapability {
+        PartialDecoderCapability {
+            partial_read: false,
+            partial_decode: false,
+        }
+    }
+
+    fn partial_encoder_capability(&self) -> PartialEncoderCapability {
+        PartialEncoderCapability {
+            partial_encode: false,
+        }
+    }
+}
+
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+impl BytesToBytesCodecTraits for N5Lz4Codec {
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn BytesToBytesCodecTraits> {
+        self
+    }
+
+    fn recommended_concurrency(
+        &self,
+        _decoded_representation: &BytesRepresentation,
+    ) -> Result<RecommendedConcurrency, CodecError> {
+        Ok(RecommendedConcurrency::new_maximum(1))
+    }
+
+    fn encode<'a>(
+        &self,
+        decoded_value: ArrayBytesRaw<'a>,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        let n_blocks = decoded_value.len().div_ceil(self.block_size as usize);
+        let mut out = Vec::with_capacity(decoded_value.len() + HEADER_LENGTH * (n_blocks + 1));
+        for block in decoded_value.chunks(self.block_size as usize) {
+            self.encode_block(block, &mut out);
+        }
+        self.encode_terminator(&mut out);
+        Ok(Cow::Owned(out))
+    }
+
+    fn decode<'a>(
+        &self,
+        encoded_value: ArrayBytesRaw<'a>,
+        _decoded_representation: &BytesRepresentation,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        Ok(Cow::Owned(decode_stream(&encoded_value)?))
+    }
+
+    fn encoded_representation(
+        &self,
+        decoded_representation: &BytesRepresentation,
+    ) -> BytesRepresentation {
+        // blocks which would grow are stored raw, so the overhead is a header per block plus the terminator
+        decoded_representation
+            .size()
+            .map_or(BytesRepresentation::UnboundedSize, |size| {
+                let n_blocks = size.div_ceil(u64::from(self.block_size));
+                BytesRepresentation::BoundedSize(size + HEADER_LENGTH as u64 * (n_blocks + 1))
+            })
+    }
+}
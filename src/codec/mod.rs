@@ -1,17 +1,100 @@
 use std::borrow::Cow;
 use std::num::NonZeroU64;
 use std::ops::{Deref, Range};
+use std::sync::Arc;
 use zarrs::array::codec::api::{
-    ArrayBytes, ArrayBytesOffsets, ArrayBytesVariableLength, CodecError,
+    ArrayBytes, ArrayBytesOffsets, ArrayBytesRaw, ArrayBytesVariableLength, BytesRepresentation,
+    BytesToBytesCodecTraits, Codec, CodecError, CodecMetadataOptions, CodecOptions,
 };
 use zarrs::array::{DataType, FillValue};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::{PluginCreateError, ZarrVersion};
 
 mod default;
-pub use default::{N5DefaultCodec, N5DefaultCodecConfiguration};
+pub use default::{N5DefaultCodec, N5DefaultCodecConfiguration, N5EdgeBlockPolicy};
 
-// TODO
-// ?lz4
-// ?xz
+mod lz4;
+pub use lz4::{N5Lz4Codec, N5Lz4CodecConfiguration};
+
+mod object;
+pub use object::{N5ObjectCodec, N5ObjectCodecConfiguration};
+
+mod string;
+pub use string::N5StringCodec;
+
+mod varlength;
+pub use varlength::{N5VarLengthCodec, N5VarLengthCodecConfiguration};
+
+mod xz;
+pub use xz::{N5XzCodec, N5XzCodecConfiguration};
+
+#[cfg(feature = "jpeg")]
+mod jpeg;
+#[cfg(feature = "jpeg")]
+pub use jpeg::{N5JpegCodec, N5JpegCodecConfiguration};
+
+/// Create the compression codecs for the body of blocks which are read as single elements.
+fn body_codecs_from_metadata(
+    metadatas: &[MetadataV3],
+) -> Result<Vec<Arc<dyn BytesToBytesCodecTraits>>, PluginCreateError> {
+    metadatas
+        .iter()
+        .map(|metadata| match Codec::from_metadata(metadata)? {
+            Codec::BytesToBytes(codec) => Ok(codec),
+            _ => Err(PluginCreateError::Other(format!(
+                "N5 block bodies can only be compressed with bytes-to-bytes codecs, got {}",
+                metadata.name()
+            ))),
+        })
+        .collect()
+}
+
+/// Metadata for the compression codecs of blocks which are read as single elements.
+fn body_codecs_metadata(
+    codecs: &[Arc<dyn BytesToBytesCodecTraits>],
+    version: ZarrVersion,
+    options: &CodecMetadataOptions,
+) -> Vec<MetadataV3> {
+    codecs
+        .iter()
+        .filter_map(|codec| {
+            let name = codec.name(version)?;
+            Some(match codec.configuration(version, options) {
+                Some(config) => MetadataV3::new_with_configuration(name, config),
+                None => MetadataV3::new(name),
+            })
+        })
+        .collect()
+}
+
+/// Decompress a block body with the given codecs.
+fn decompress_body<'a>(
+    codecs: &[Arc<dyn BytesToBytesCodecTraits>],
+    body: &'a [u8],
+    options: &CodecOptions,
+) -> Result<ArrayBytesRaw<'a>, CodecError> {
+    let mut bytes: ArrayBytesRaw = Cow::Borrowed(body);
+    for codec in codecs.iter().rev() {
+        bytes = codec.decode(bytes, &BytesRepresentation::UnboundedSize, options)?;
+    }
+    Ok(bytes)
+}
+
+/// Array bytes for a chunk of a single variable-length element.
+fn single_element<'a>(
+    element: ArrayBytesRaw<'a>,
+    shape: &[NonZeroU64],
+) -> Result<ArrayBytes<'a>, CodecError> {
+    if shape.iter().any(|n| n.get() != 1) {
+        return Err(CodecError::Other(format!(
+            "N5 blocks are read as single elements, but the chunk shape is {shape:?}"
+        )));
+    }
+    let offsets = ArrayBytesOffsets::new(vec![0, element.len()])?;
+    Ok(ArrayBytes::Variable(ArrayBytesVariableLength::new(
+        element, offsets,
+    )?))
+}
 
 struct ShapeRectifier<'a> {
     array_bytes: ArrayBytes<'a>,
@@ -53,7 +136,6 @@ impl<'a> ShapeRectifier<'a> {
                 self.handle_fixed(cow, width)
             }
             ArrayBytes::Variable(abvl) => {
-                // This is unreachable for now but is along the right lines if implemented in future.
                 let offsets = abvl.offsets().deref();
                 let bytes = abvl.bytes().deref();
                 self.handle_variable(bytes, offsets)
@@ -90,7 +172,6 @@ impl<'a> ShapeRectifier<'a> {
         Ok(ArrayBytes::Fixed(Cow::Owned(out)))
     }
 
-    /// This is unreachable for now but is along the right lines if implemented in future.
     fn handle_variable(
         &self,
         bytes: &[u8],
@@ -0,0 +1,178 @@
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use zarrs::array::codec::api::{
+    ArrayBytes, ArrayBytesRaw, ArrayCodecTraits, ArrayToBytesCodecTraits, BytesRepresentation,
+    BytesToBytesCodecTraits, Codec, CodecError, CodecMetadataOptions, CodecOptions, CodecPluginV3,
+    CodecTraits, CodecTraitsV3, PartialDecoderCapability, PartialEncoderCapability,
+    RecommendedConcurrency,
+};
+use zarrs::array::{DataType, FillValue};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::{PluginCreateError, ZarrVersion};
+
+use crate::chunk::{N5BlockHeader, N5BlockMode};
+
+zarrs::plugin::impl_extension_aliases!(N5ObjectCodec, v3: "n5_object", ["zarrs.n5_object"]);
+inventory::submit! {
+    CodecPluginV3::new::<N5ObjectCodec>()
+}
+
+/// Codec for N5 object-mode blocks, which hold an opaque payload (e.g. a serialized Java object).
+///
+/// Each block is decoded as a single element of the Zarr `bytes` data type,
+/// so the Zarr array has one element per N5 block (and a chunk shape of 1 in every dimension).
+/// The element holds the block's decompressed payload, uninterpreted.
+/// Missing blocks are read as empty.
+///
+/// On decode, validates and strips the header, then applies the configured compression codec, if any.
+/// Blocks cannot be encoded.
+#[derive(Debug, Clone)]
+pub struct N5ObjectCodec {
+    /// May contain a single bytes-to-bytes codec representing the N5 compression.
+    codecs: Vec<Arc<dyn BytesToBytesCodecTraits>>,
+}
+
+impl N5ObjectCodec {
+    /// Create a codec with the given compression.
+    pub fn new(compression: Option<Arc<dyn BytesToBytesCodecTraits>>) -> Self {
+        Self {
+            codecs: compression.into_iter().collect(),
+        }
+    }
+
+    pub fn new_with_configuration(
+        configuration: &N5ObjectCodecConfiguration,
+    ) -> Result<Self, PluginCreateError> {
+        Ok(Self {
+            codecs: super::body_codecs_from_metadata(&configuration.codecs)?,
+        })
+    }
+}
+
+/// Configuration for [N5ObjectCodec].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct N5ObjectCodecConfiguration {
+    /// Codecs to apply to the block body, i.e. after stripping the N5 block header.
+    codecs: Vec<MetadataV3>,
+}
+
+impl CodecTraitsV3 for N5ObjectCodec {
+    fn create(metadata: &MetadataV3) -> Result<Codec, PluginCreateError>
+    where
+        Self: Sized,
+    {
+        let configuration = metadata.to_typed_configuration()?;
+        let codec = Arc::new(N5ObjectCodec::new_with_configuration(&configuration)?);
+        Ok(Codec::ArrayToBytes(codec))
+    }
+}
+
+impl CodecTraits for N5ObjectCodec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn configuration(
+        &self,
+        version: ZarrVersion,
+        options: &CodecMetadataOptions,
+    ) -> Option<zarrs::metadata::Configuration> {
+        let config = N5ObjectCodecConfiguration {
+            codecs: super::body_codecs_metadata(&self.codecs, version, options),
+        };
+        let val =
+            serde_json::to_value(config).expect("N5 object configuration should be serializable");
+        let serde_json::Value::Object(map) = val else {
+            panic!("N5 object configuration should serialize to a JSON object");
+        };
+        Some(map.into())
+    }
+
+    fn partial_decoder_capability(&self) -> PartialDecoderCapability {
+        PartialDecoderCapability {
+            partial_read: false,
+            partial_decode: false,
+        }
+    }
+
+    fn partial_encoder_capability(&self) -> PartialEncoderCapability {
+        PartialEncoderCapability {
+            partial_encode: false,
+        }
+    }
+}
+
+impl ArrayCodecTraits for N5ObjectCodec {
+    fn recommended_concurrency(
+        &self,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+    ) -> Result<RecommendedConcurrency, CodecError> {
+        Ok(RecommendedConcurrency::new_maximum(1))
+    }
+}
+
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+impl ArrayToBytesCodecTraits for N5ObjectCodec {
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn ArrayToBytesCodecTraits> {
+        self
+    }
+
+    fn encoded_representation(
+        &self,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+    ) -> Result<BytesRepresentation, CodecError> {
+        Ok(BytesRepresentation::UnboundedSize)
+    }
+
+    fn encode<'a>(
+        &self,
+        _bytes: ArrayBytes<'a>,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        Err(CodecError::Other(
+            "writing N5 object blocks is not supported".to_string(),
+        ))
+    }
+
+    fn decode<'a>(
+        &self,
+        bytes: ArrayBytesRaw<'a>,
+        shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'a>, CodecError> {
+        let header = N5BlockHeader::from_bytes(&bytes)
+            .map_err(|e| CodecError::Other(format!("N5 block header could not be parsed: {e}")))?;
+        let N5BlockMode::Object { num_el } = header.mode else {
+            return Err(CodecError::Other(format!(
+                "expected an N5 object block, got {:?}",
+                header.mode
+            )));
+        };
+        let payload =
+            super::decompress_body(&self.codecs, &bytes[header.data_offset()..], options)?;
+        if payload.len() != num_el as usize {
+            return Err(CodecError::Other(format!(
+                "N5 object block has {} bytes, expected {num_el}",
+                payload.len()
+            )));
+        }
+        Ok(super::single_element(payload, shape)?.into_owned())
+    }
+}
@@ -0,0 +1,172 @@
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use zarrs::array::codec::api::{
+    ArrayBytes, ArrayBytesOffsets, ArrayBytesRaw, ArrayBytesVariableLength, ArrayCodecTraits,
+    ArrayToBytesCodecTraits, BytesRepresentation, Codec, CodecError, CodecMetadataOptions,
+    CodecOptions, CodecPluginV3, CodecTraits, CodecTraitsV3, PartialDecoderCapability,
+    PartialEncoderCapability, RecommendedConcurrency,
+};
+use zarrs::array::{DataType, FillValue};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::PluginCreateError;
+
+zarrs::plugin::impl_extension_aliases!(N5StringCodec, v3: "n5_string", ["zarrs.n5_string"]);
+inventory::submit! {
+    CodecPluginV3::new::<N5StringCodec>()
+}
+
+const SEPARATOR: u8 = b'\0';
+
+/// Serialization of `string` elements as written by n5-java: UTF-8, each followed by a NUL byte.
+///
+/// Used inside an [crate::N5DefaultCodec] in place of the big-endian bytes codec,
+/// so does not handle the block header or compression.
+/// Strings cannot contain NUL.
+///
+/// n5-java splits blocks on NUL, which drops trailing empty strings,
+/// so fewer strings than the block has elements are padded with empty strings when decoding.
+/// Blocks without the final NUL are also accepted.
+#[derive(Debug, Clone, Default)]
+pub struct N5StringCodec;
+
+impl N5StringCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn num_elements(shape: &[NonZeroU64]) -> usize {
+    shape.iter().map(|n| n.get() as usize).product()
+}
+
+impl CodecTraitsV3 for N5StringCodec {
+    fn create(_metadata: &MetadataV3) -> Result<Codec, PluginCreateError>
+    where
+        Self: Sized,
+    {
+        Ok(Codec::ArrayToBytes(Arc::new(N5StringCodec::new())))
+    }
+}
+
+impl CodecTraits for N5StringCodec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn configuration(
+        &self,
+        _version: zarrs::plugin::ZarrVersion,
+        _options: &CodecMetadataOptions,
+    ) -> Option<zarrs::metadata::Configuration> {
+        // codecs without a configuration are dropped from a codec chain's metadata
+        Some(zarrs::metadata::Configuration::default())
+    }
+
+    fn partial_decoder_capability(&self) -> PartialDecoderCapability {
+        PartialDecoderCapability {
+            partial_read: false,
+            partial_decode: false,
+        }
+    }
+
+    fn partial_encoder_capability(&self) -> PartialEncoderCapability {
+        PartialEncoderCapability {
+            partial_encode: false,
+        }
+    }
+}
+
+impl ArrayCodecTraits for N5StringCodec {
+    fn recommended_concurrency(
+        &self,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+    ) -> Result<RecommendedConcurrency, CodecError> {
+        Ok(RecommendedConcurrency::new_maximum(1))
+    }
+}
+
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+impl ArrayToBytesCodecTraits for N5StringCodec {
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn ArrayToBytesCodecTraits> {
+        self
+    }
+
+    fn encoded_representation(
+        &self,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+    ) -> Result<BytesRepresentation, CodecError> {
+        Ok(BytesRepresentation::UnboundedSize)
+    }
+
+    fn encode<'a>(
+        &self,
+        bytes: ArrayBytes<'a>,
+        shape: &[NonZeroU64],
+        data_type: &DataType,
+        _fill_value: &FillValue,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        bytes.validate(num_elements(shape) as u64, data_type)?;
+        let ArrayBytes::Variable(strings) = bytes else {
+            return Err(CodecError::Other(format!(
+                "N5 string elements must be variable-length, got {data_type}"
+            )));
+        };
+        if strings.bytes().contains(&SEPARATOR) {
+            return Err(CodecError::Other(
+                "N5 strings cannot contain NUL".to_string(),
+            ));
+        }
+        let offsets = strings.offsets();
+        let mut out = Vec::with_capacity(strings.bytes().len() + offsets.len());
+        for window in offsets.windows(2) {
+            out.extend_from_slice(&strings.bytes()[window[0]..window[1]]);
+            out.push(SEPARATOR);
+        }
+        Ok(out.into())
+    }
+
+    fn decode<'a>(
+        &self,
+        bytes: ArrayBytesRaw<'a>,
+        shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytes<'a>, CodecError> {
+        std::str::from_utf8(&bytes)
+            .map_err(|e| CodecError::Other(format!("N5 strings are not valid UTF-8: {e}")))?;
+        let expected = num_elements(shape);
+        let mut offsets = Vec::with_capacity(expected + 1);
+        offsets.push(0);
+        let mut content = Vec::with_capacity(bytes.len());
+        let unterminated = bytes.strip_suffix(&[SEPARATOR]).unwrap_or(&bytes);
+        for element in unterminated.split(|b| *b == SEPARATOR) {
+            content.extend_from_slice(element);
+            offsets.push(content.len());
+        }
+        if offsets.len() > expected + 1 {
+            return Err(CodecError::Other(format!(
+                "N5 block has {} strings, expected {expected}",
+                offsets.len() - 1
+            )));
+        }
+        // trailing empty strings
+        offsets.resize(expected + 1, content.len());
+        Ok(ArrayBytes::Variable(ArrayBytesVariableLength::new(
+            content,
+            ArrayBytesOffsets::new(offsets)?,
+        )?))
+    }
+}
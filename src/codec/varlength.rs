@@ -0,0 +1,232 @@
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use zarrs::array::codec::api::{
+    ArrayBytes, ArrayBytesRaw, ArrayCodecTraits, ArrayToBytesCodecTraits, BytesRepresentation,
+    BytesToBytesCodecTraits, Codec, CodecError, CodecMetadataOptions, CodecOptions, CodecPluginV3,
+    CodecTraits, CodecTraitsV3, PartialDecoderCapability, PartialEncoderCapability,
+    RecommendedConcurrency,
+};
+use zarrs::array::{DataType, FillValue};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::{PluginCreateError, ZarrVersion};
+
+use crate::chunk::{N5BlockHeader, N5BlockMode};
+
+zarrs::plugin::impl_extension_aliases!(N5VarLengthCodec, v3: "n5_varlength", ["zarrs.n5_varlength"]);
+inventory::submit! {
+    CodecPluginV3::new::<N5VarLengthCodec>()
+}
+
+/// Codec for N5 varlength-mode blocks, which hold any number of elements regardless of their header shape.
+///
+/// Each block is decoded as a single element of the Zarr `bytes` data type,
+/// so the Zarr array has one element per N5 block (and a chunk shape of 1 in every dimension).
+/// The element holds the block's `num_el` elements of the N5 `dataType`, in native byte order.
+/// Missing blocks are read as empty.
+///
+/// The mode is read from each block's header, so arrays mixing block modes can be read:
+/// a default-mode block (which n5-java writes whenever a block holds exactly as many elements as its shape)
+/// is read as an element holding each point of its header shape, in N5 (column-major) order.
+///
+/// On decode, validates and strips the header, then applies the configured compression codec, if any.
+/// Blocks cannot be encoded.
+#[derive(Debug, Clone)]
+pub struct N5VarLengthCodec {
+    /// The N5 `dataType` of the elements in each block.
+    data_type: String,
+    /// Width in bytes of the N5 `dataType`.
+    element_size: usize,
+    /// May contain a single bytes-to-bytes codec representing the N5 compression.
+    codecs: Vec<Arc<dyn BytesToBytesCodecTraits>>,
+}
+
+impl N5VarLengthCodec {
+    /// Create a codec for blocks of the given N5 `dataType`, with the given compression.
+    pub fn new(
+        compression: Option<Arc<dyn BytesToBytesCodecTraits>>,
+        data_type: &str,
+    ) -> crate::Result<Self> {
+        let element_size = crate::metadata::data_type_size(data_type).ok_or_else(|| {
+            crate::Error::general(format!("unsupported N5 varlength data type: {data_type}"))
+        })?;
+        Ok(Self {
+            data_type: data_type.to_string(),
+            element_size,
+            codecs: compression.into_iter().collect(),
+        })
+    }
+
+    pub fn new_with_configuration(
+        configuration: &N5VarLengthCodecConfiguration,
+    ) -> Result<Self, PluginCreateError> {
+        let codecs = super::body_codecs_from_metadata(&configuration.codecs)?;
+        let mut out = Self::new(None, &configuration.data_type)
+            .map_err(|e| PluginCreateError::Other(e.to_string()))?;
+        out.codecs = codecs;
+        Ok(out)
+    }
+
+    /// The N5 `dataType` of the elements in each block.
+    pub fn data_type(&self) -> &str {
+        &self.data_type
+    }
+
+    /// Decompress a block body and convert its elements to native byte order.
+    fn decode_body(
+        &self,
+        body: &[u8],
+        num_el: u32,
+        options: &CodecOptions,
+    ) -> Result<Vec<u8>, CodecError> {
+        let mut bytes = super::decompress_body(&self.codecs, body, options)?.into_owned();
+        let expected = num_el as usize * self.element_size;
+        if bytes.len() != expected {
+            return Err(CodecError::Other(format!(
+                "N5 varlength block of {num_el} {} elements has {} bytes, expected {expected}",
+                self.data_type,
+                bytes.len()
+            )));
+        }
+        if cfg!(target_endian = "little") && self.element_size > 1 {
+            for element in bytes.chunks_exact_mut(self.element_size) {
+                element.reverse();
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Configuration for [N5VarLengthCodec].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct N5VarLengthCodecConfiguration {
+    /// The N5 `dataType` of the elements in each block.
+    data_type: String,
+    /// Codecs to apply to the block body, i.e. after stripping the N5 block header.
+    codecs: Vec<MetadataV3>,
+}
+
+impl CodecTraitsV3 for N5VarLengthCodec {
+    fn create(metadata: &MetadataV3) -> Result<Codec, PluginCreateError>
+    where
+        Self: Sized,
+    {
+        let configuration = metadata.to_typed_configuration()?;
+        let codec = Arc::new(N5VarLengthCodec::new_with_configuration(&configuration)?);
+        Ok(Codec::ArrayToBytes(codec))
+    }
+}
+
+impl CodecTraits for N5VarLengthCodec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn configuration(
+        &self,
+        version: ZarrVersion,
+        options: &CodecMetadataOptions,
+    ) -> Option<zarrs::metadata::Configuration> {
+        let config = N5VarLengthCodecConfiguration {
+            data_type: self.data_type.clone(),
+            codecs: super::body_codecs_metadata(&self.codecs, version, options),
+        };
+        let val = serde_json::to_value(config)
+            .expect("N5 varlength configuration should be serializable");
+        let serde_json::Value::Object(map) = val else {
+            panic!("N5 varlength configuration should serialize to a JSON object");
+        };
+        Some(map.into())
+    }
+
+    fn partial_decoder_capability(&self) -> PartialDecoderCapability {
+        PartialDecoderCapability {
+            partial_read: false,
+            partial_decode: false,
+        }
+    }
+
+    fn partial_encoder_capability(&self) -> PartialEncoderCapability {
+        PartialEncoderCapability {
+            partial_encode: false,
+        }
+    }
+}
+
+impl ArrayCodecTraits for N5VarLengthCodec {
+    fn recommended_concurrency(
+        &self,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+    ) -> Result<RecommendedConcurrency, CodecError> {
+        Ok(RecommendedConcurrency::new_maximum(1))
+    }
+}
+
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+impl ArrayToBytesCodecTraits for N5VarLengthCodec {
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn ArrayToBytesCodecTraits> {
+        self
+    }
+
+    fn encoded_representation(
+        &self,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+    ) -> Result<BytesRepresentation, CodecError> {
+        Ok(BytesRepresentation::UnboundedSize)
+    }
+
+    fn encode<'a>(
+        &self,
+        _bytes: ArrayBytes<'a>,
+        _shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        Err(CodecError::Other(
+            "writing N5 varlength blocks is not supported".to_string(),
+        ))
+    }
+
+    fn decode<'a>(
+        &self,
+        bytes: ArrayBytesRaw<'a>,
+        shape: &[NonZeroU64],
+        _data_type: &DataType,
+        _fill_value: &FillValue,
+        options: &CodecOptions,
+    ) -> Result<ArrayBytes<'a>, CodecError> {
+        let header = N5BlockHeader::from_bytes(&bytes)
+            .map_err(|e| CodecError::Other(format!("N5 block header could not be parsed: {e}")))?;
+        let num_el = match header.mode {
+            N5BlockMode::VarLength { num_el } => num_el,
+            N5BlockMode::Default => {
+                let num_el = header.shape.iter().map(|n| u64::from(*n)).product::<u64>();
+                u32::try_from(num_el).map_err(|_| {
+                    CodecError::Other(format!(
+                        "N5 block of shape {:?} has too many elements to read as one element",
+                        header.shape
+                    ))
+                })?
+            }
+            N5BlockMode::Object { .. } => {
+                return Err(CodecError::Other(
+                    "N5 object blocks cannot be read as varlength blocks".to_string(),
+                ));
+            }
+        };
+        let element = self.decode_body(&bytes[header.data_offset()..], num_el, options)?;
+        Ok(super::single_element(element.into(), shape)?.into_owned())
+    }
+}
@@ -0,0 +1,153 @@
+use std::borrow::Cow;
+use std::io::Read;
+use std::sync::Arc;
+
+use liblzma::bufread::{XzDecoder, XzEncoder};
+use serde::{Deserialize, Serialize};
+use zarrs::array::codec::api::{
+    ArrayBytesRaw, BytesRepresentation, BytesToBytesCodecTraits, Codec, CodecError,
+    CodecMetadataOptions, CodecOptions, CodecPluginV3, CodecTraits, CodecTraitsV3,
+    PartialDecoderCapability, PartialEncoderCapability, RecommendedConcurrency,
+};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::PluginCreateError;
+
+zarrs::plugin::impl_extension_aliases!(N5XzCodec, v3: "n5_xz", ["zarrs.n5_xz"]);
+inventory::submit! {
+    CodecPluginV3::new::<N5XzCodec>()
+}
+
+const MAX_PRESET: u32 = 9;
+
+/// XZ compression, as written by n5-java through commons-compress' `XZCompressorOutputStream`.
+///
+/// Encodes a single `.xz` stream with a CRC64 check.
+#[derive(Debug, Clone)]
+pub struct N5XzCodec {
+    preset: u32,
+}
+
+impl N5XzCodec {
+    /// Create a codec with the given preset, which must be in the range 0..=9.
+    pub fn new(preset: u32) -> crate::Result<Self> {
+        if preset > MAX_PRESET {
+            return Err(crate::Error::general(format!(
+                "invalid XZ preset {preset}, must be in the range 0..={MAX_PRESET}"
+            )));
+        }
+        Ok(Self { preset })
+    }
+
+    pub fn new_with_configuration(
+        configuration: &N5XzCodecConfiguration,
+    ) -> Result<Self, PluginCreateError> {
+        Self::new(configuration.preset).map_err(|e| PluginCreateError::Other(e.to_string()))
+    }
+
+    pub fn preset(&self) -> u32 {
+        self.preset
+    }
+}
+
+/// Configuration for [N5XzCodec].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct N5XzCodecConfiguration {
+    /// Compression preset in the range 0..=9.
+    pub preset: u32,
+}
+
+impl CodecTraitsV3 for N5XzCodec {
+    fn create(metadata: &MetadataV3) -> Result<Codec, PluginCreateError>
+    where
+        Self: Sized,
+    {
+        let configuration = metadata.to_typed_configuration()?;
+        let codec = Arc::new(N5XzCodec::new_with_configuration(&configuration)?);
+        Ok(Codec::BytesToBytes(codec))
+    }
+}
+
+impl CodecTraits for N5XzCodec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn configuration(
+        &self,
+        _version: zarrs::plugin::ZarrVersion,
+        _options: &CodecMetadataOptions,
+    ) -> Option<zarrs::metadata::Configuration> {
+        let config = N5XzCodecConfiguration {
+            preset: self.preset,
+        };
+        let val = serde_json::to_value(config).expect("XZ configuration should be serializable");
+        let serde_json::Value::Object(map) = val else {
+            panic!("XZ configuration should serialize to a JSON object");
+        };
+        Some(map.into())
+    }
+
+    fn partial_decoder_capability(&self) -> PartialDecoderCapability {
+        PartialDecoderCapability {
+            partial_read: false,
+            partial_decode: false,
+        }
+    }
+
+    fn partial_encoder_capability(&self) -> PartialEncoderCapability {
+        PartialEncoderCapability {
+            partial_encode: false,
+        }
+    }
+}
+
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+impl BytesToBytesCodecTraits for N5XzCodec {
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn BytesToBytesCodecTraits> {
+        self
+    }
+
+    fn recommended_concurrency(
+        &self,
+        _decoded_representation: &BytesRepresentation,
+    ) -> Result<RecommendedConcurrency, CodecError> {
+        Ok(RecommendedConcurrency::new_maximum(1))
+    }
+
+    fn encode<'a>(
+        &self,
+        decoded_value: ArrayBytesRaw<'a>,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        let mut encoder = XzEncoder::new(decoded_value.as_ref(), self.preset);
+        let mut out = Vec::default();
+        encoder.read_to_end(&mut out)?;
+        Ok(Cow::Owned(out))
+    }
+
+    fn decode<'a>(
+        &self,
+        encoded_value: ArrayBytesRaw<'a>,
+        _decoded_representation: &BytesRepresentation,
+        _options: &CodecOptions,
+    ) -> Result<ArrayBytesRaw<'a>, CodecError> {
+        let mut decoder = XzDecoder::new(encoded_value.as_ref());
+        let mut out = Vec::default();
+        decoder.read_to_end(&mut out)?;
+        Ok(Cow::Owned(out))
+    }
+
+    fn encoded_representation(
+        &self,
+        _decoded_representation: &BytesRepresentation,
+    ) -> BytesRepresentation {
+        BytesRepresentation::UnboundedSize
+    }
+}
@@ -31,7 +31,7 @@ fn meta_key_any(path: &NodePath, metadata_file_name: &str) -> StoreKey {
     }
 }
 
-fn meta_key_n5(path: &NodePath) -> StoreKey {
+pub(crate) fn meta_key_n5(path: &NodePath) -> StoreKey {
     meta_key_any(path, N5_METADATA_KEY)
 }
 
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use zarrs::{
+    array::{Array, ArrayBytes},
+    node::NodePath,
+    storage::{
+        Bytes, ListableStorageTraits, ReadableStorageTraits, StorageError, StoreKey, StorePrefix,
+        WritableStorageTraits,
+    },
+};
+
+use crate::{
+    N5BlockHeader, N5BlockMode, N5EdgeBlockPolicy, N5GroupMetadata, N5Metadata, N5StoreAdapter,
+    convert::meta_key_n5, storage::array_block_keys,
+};
+
+/// Version of the N5 specification written to new hierarchy roots.
+pub const N5_VERSION: &str = "4.0.0";
+
+/// Create an N5 hierarchy root at the given path, with the given attributes.
+///
+/// This writes an `attributes.json` containing the `n5` version key.
+/// If there is already N5 metadata at the path, the attributes are merged into it
+/// (see [N5Metadata::merge_into]), and an existing version is retained.
+///
+/// Fails if any ancestor of the path is already an N5 hierarchy root.
+pub fn create_n5_root<S: ReadableStorageTraits + WritableStorageTraits + ?Sized>(
+    store: &S,
+    path: &NodePath,
+    attributes: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), StorageError> {
+    for ancestor in ancestors(path) {
+        let key = meta_key_n5(&ancestor);
+        if read_n5_metadata(store, &key)?.is_some_and(|m| m.is_root()) {
+            return Err(StorageError::Other(format!(
+                "cannot create N5 root at {path} inside existing N5 root at {ancestor}"
+            )));
+        }
+    }
+
+    let key = meta_key_n5(path);
+    let existing = read_n5_metadata(store, &key)?;
+    let n5_version = match existing {
+        Some(m) if m.is_root() => None,
+        _ => Some(N5_VERSION.to_string()),
+    };
+    write_n5_metadata(
+        store,
+        &key,
+        N5GroupMetadata {
+            n5_version,
+            attributes,
+        },
+    )
+}
+
+/// Create an N5 group at the given path, with optional attributes.
+///
+/// This writes an `attributes.json` at the path, merging the attributes into any existing N5 metadata
+/// (see [N5Metadata::merge_into]).
+/// Per the N5 spec, intermediate groups between the path and the hierarchy root need no metadata,
+/// so nothing is written for them.
+pub fn create_n5_group<S: ReadableStorageTraits + WritableStorageTraits + ?Sized>(
+    store: &S,
+    path: &NodePath,
+    attributes: Option<serde_json::Map<String, serde_json::Value>>,
+) -> Result<(), StorageError> {
+    write_n5_metadata(
+        store,
+        &meta_key_n5(path),
+        N5GroupMetadata {
+            n5_version: None,
+            attributes: attributes.unwrap_or_default(),
+        },
+    )
+}
+
+/// Change the dimensions of an existing N5 array, returning the resized array.
+///
+/// The `dimensions` in `attributes.json` are updated in place; all other keys are retained.
+/// If `prune` is true, blocks which fall outside the new grid are erased.
+/// Blocks on the new edge whose header shape extends past the new bounds are rewritten
+/// according to the adapter's [N5EdgeBlockPolicy], so that truncated blocks keep valid header shapes
+/// and elements which are now out of bounds are discarded (or, for padded blocks, replaced with the fill value).
+///
+/// This cannot be done with [Array::set_shape],
+/// as the [N5StoreAdapter] refuses to change the structure of existing arrays.
+/// Resizing is not atomic.
+pub fn resize_n5_array<
+    S: ReadableStorageTraits + WritableStorageTraits + ListableStorageTraits + 'static,
+>(
+    store: Arc<N5StoreAdapter<S>>,
+    path: &str,
+    dimensions: Vec<u64>,
+    prune: bool,
+) -> crate::Result<Array<N5StoreAdapter<S>>> {
+    let node_path = NodePath::new(path).map_err(crate::Error::wrap)?;
+    let key = meta_key_n5(&node_path);
+    let inner = store.inner();
+
+    let Some(bytes) = inner.get(&key).map_err(crate::Error::wrap)? else {
+        return Err(crate::Error::general(format!("no N5 metadata at {path}")));
+    };
+    let mut existing: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&bytes)?;
+    let N5Metadata::Array(old) =
+        serde_json::from_value(serde_json::Value::Object(existing.clone()))?
+    else {
+        return Err(crate::Error::general(format!("{path} is not an N5 array")));
+    };
+    if old.dimensions.len() != dimensions.len() {
+        return Err(crate::Error::general(format!(
+            "cannot change dimensionality of N5 array from {} to {}",
+            old.dimensions.len(),
+            dimensions.len()
+        )));
+    }
+    existing.insert("dimensions".into(), dimensions.clone().into());
+    inner
+        .set(&key, Bytes::from(serde_json::to_vec(&existing)?))
+        .map_err(crate::Error::wrap)?;
+
+    let array = Array::open(store.clone(), path).map_err(crate::Error::wrap)?;
+    let prefix = key
+        .as_str()
+        .strip_suffix(crate::N5_METADATA_KEY)
+        .and_then(|p| StorePrefix::new(p).ok())
+        .expect("N5 metadata key should be in a valid prefix");
+    let block_keys = array_block_keys(
+        &prefix,
+        inner.list_prefix(&prefix).map_err(crate::Error::wrap)?,
+        dimensions.len(),
+    );
+    for block_key in block_keys {
+        let Ok(indices) = block_key.as_str()[prefix.as_str().len()..]
+            .split('/')
+            .map(str::parse::<u64>)
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            continue;
+        };
+        if indices
+            .iter()
+            .zip(array.chunk_grid_shape())
+            .any(|(idx, n)| idx >= n)
+        {
+            if prune {
+                inner.erase(&block_key).map_err(crate::Error::wrap)?;
+            }
+            continue;
+        }
+
+        let extent = array.chunk_shape(&indices).map_err(crate::Error::wrap)?;
+        if extent == old.block_size {
+            continue;
+        }
+        let Some(block) = inner.get(&block_key).map_err(crate::Error::wrap)? else {
+            continue;
+        };
+        let header = N5BlockHeader::from_bytes(&block)?;
+        if !matches!(header.mode, N5BlockMode::Default) {
+            continue;
+        }
+        // a block is valid if it fits within the new bounds,
+        // or is padded and did not shrink, so anything past the bounds is already the fill value
+        let fits = header
+            .shape
+            .iter()
+            .zip(&extent)
+            .all(|(h, e)| u64::from(*h) <= e.get());
+        let shrunk = indices
+            .iter()
+            .zip(&old.dimensions)
+            .zip(&old.block_size)
+            .zip(&extent)
+            .any(|(((idx, dim), block), e)| {
+                let old_extent = block.get().min(dim.saturating_sub(idx * block.get()));
+                e.get() < old_extent
+            });
+        let padded = header
+            .shape
+            .iter()
+            .zip(&old.block_size)
+            .all(|(h, b)| u64::from(*h) == b.get());
+        let valid =
+            fits || (store.edge_block_policy() == N5EdgeBlockPolicy::Pad && padded && !shrunk);
+        if !valid {
+            let data: ArrayBytes<'static> =
+                array.retrieve_chunk(&indices).map_err(crate::Error::wrap)?;
+            array
+                .store_chunk(&indices, data)
+                .map_err(crate::Error::wrap)?;
+        }
+    }
+    Ok(array)
+}
+
+/// Paths of all ancestors of the given node, from the hierarchy root downwards.
+fn ancestors(path: &NodePath) -> Vec<NodePath> {
+    let mut out = Vec::default();
+    let s = path.as_str();
+    if s == "/" {
+        return out;
+    }
+    out.push(NodePath::root());
+    let mut current = String::new();
+    let mut parts: Vec<_> = s.trim_start_matches('/').split('/').collect();
+    parts.pop();
+    for part in parts {
+        current.push('/');
+        current.push_str(part);
+        out.push(NodePath::new(&current).expect("ancestor of valid path should be valid"));
+    }
+    out
+}
+
+fn read_n5_metadata<S: ReadableStorageTraits + ?Sized>(
+    store: &S,
+    key: &StoreKey,
+) -> Result<Option<N5Metadata>, StorageError> {
+    let Some(bytes) = store.get(key)? else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+        StorageError::InvalidMetadata(key.clone(), format!("could not parse N5 metadata: {e}"))
+    })
+}
+
+/// Merge N5 metadata into the `attributes.json` at the given key.
+pub(crate) fn write_n5_metadata<S: ReadableStorageTraits + WritableStorageTraits + ?Sized>(
+    store: &S,
+    key: &StoreKey,
+    metadata: impl Into<N5Metadata>,
+) -> Result<(), StorageError> {
+    let existing = match store.get(key)? {
+        Some(b) => serde_json::from_slice(&b).map_err(|e| {
+            StorageError::InvalidMetadata(
+                key.clone(),
+                format!("could not parse existing N5 metadata: {e}"),
+            )
+        })?,
+        None => Default::default(),
+    };
+    let merged = metadata.into().merge_into(existing).map_err(|e| {
+        StorageError::InvalidMetadata(key.clone(), format!("could not merge N5 metadata: {e}"))
+    })?;
+    let bytes = serde_json::to_vec(&merged).expect("N5 metadata should be serializable");
+    store.set(key, Bytes::from(bytes))
+}
@@ -4,11 +4,30 @@
 //! This crate is comprises
 //!
 //! - [N5StoreAdapter], which wraps other [zarrs] stores
-//!   - implements reading and listing, blocking and async, as supported by the wrapped store
+//!   - implements reading, writing and listing, blocking and async, as supported by the wrapped store
+//!   - writes of Zarr metadata are converted into N5 metadata, so arrays and groups can be created with [zarrs::array::ArrayBuilder] and [zarrs::group::GroupBuilder]
 //!   - you may want to wrap this in an [ImplicitGroupStoreAdapter] to treat missing N5 metadata as empty groups, per the N5 spec
-//! - [N5Codec], an array-to-bytes codec which handles the N5 block header, bigendian byte order, block data transposition, and compression
-//!   - varlen and object chunk modes are not supported
+//! - [N5DefaultCodec], an array-to-bytes codec which handles the N5 block header, bigendian byte order, block data transposition, and compression
+//!   - blocks can be both decoded and encoded
+//!   - blocks containing only the fill value (always 0 for N5) are not written, as N5 readers treat missing blocks as zeros
+//!   - edge blocks are written truncated to the array bounds (as n5-java does) or padded to the full block size, per [N5EdgeBlockPolicy]
+//!   - subsets of uncompressed blocks are read by byte range, rather than reading the whole block
+//!   - with zarrs' experimental partial encoding, writes to part of a block keep its existing header shape, and uncompressed blocks are updated in place
+//!   - optionally, each block's compression is detected from its magic bytes, for datasets whose blocks do not all match the metadata
+//!   - varlength blocks in default-mode arrays are read as their elements followed by the fill value
+//!   - varlength and object block modes are otherwise handled by separate codecs
 //!   - not all N5 compressors are supported
+//!   - `string` arrays are read and written as Zarr `string` arrays, with their elements serialized by [N5StringCodec]
+//! - [N5VarLengthCodec], an array-to-bytes codec which reads each varlength-mode block as a single Zarr `bytes` element (see [N5ArrayMode::VarLength])
+//!   - default-mode blocks can also be read this way, as the mode is read from each block
+//! - [N5ObjectCodec], an array-to-bytes codec which reads each object-mode block's payload as a single Zarr `bytes` element (see [N5ArrayMode::Object])
+//! - [N5Lz4Codec], a bytes-to-bytes codec for N5's LZ4 compression, which uses lz4-java's block stream framing rather than the LZ4 frame format
+//! - [N5XzCodec], a bytes-to-bytes codec for N5's XZ compression
+//! - `N5JpegCodec` (with the `jpeg` feature), a decode-only bytes-to-bytes codec for n5-jpeg's `uint8` blocks
+//! - [N5CompressionPlugin], which registers third-party N5 compressions (by their `type`) with [inventory]
+//! - [N5ArrayBuilder], which creates N5 arrays from N5 parameters and returns a [zarrs::array::Array] over the [N5StoreAdapter]
+//! - [create_n5_root] and [create_n5_group], which start a new N5 hierarchy and add groups to it
+//! - [resize_n5_array], which changes the dimensions of an existing N5 array
 //! - [convert_n5_node] and [convert_n5_hierarchy], which adds Zarr metadata to N5 objects to allow them to be read as Zarr without the [N5StoreAdapter]/ [ImplicitGroupStoreAdapter] wrappers
 //!   - this functionality is experimental and relies on unstable Zarr extensions which may not be supported by other implementations
 //!
@@ -17,11 +36,20 @@
 //! This converted metadata contains configuration for the N5-specific chunk key encoding and codec plugins,
 //! so regular [zarrs] APIs can be used transparently.
 
+mod builder;
+pub use builder::N5ArrayBuilder;
+
 mod chunk;
 pub use chunk::{N5BlockHeader, N5BlockMode};
 
 mod codec;
-pub use codec::{N5DefaultCodec, N5DefaultCodecConfiguration};
+pub use codec::{
+    N5DefaultCodec, N5DefaultCodecConfiguration, N5EdgeBlockPolicy, N5Lz4Codec,
+    N5Lz4CodecConfiguration, N5ObjectCodec, N5ObjectCodecConfiguration, N5StringCodec,
+    N5VarLengthCodec, N5VarLengthCodecConfiguration, N5XzCodec, N5XzCodecConfiguration,
+};
+#[cfg(feature = "jpeg")]
+pub use codec::{N5JpegCodec, N5JpegCodecConfiguration};
 
 mod error;
 pub use error::{Error, Result};
@@ -29,12 +57,18 @@ pub use error::{Error, Result};
 mod metadata;
 pub use metadata::{N5ArrayMetadata, N5Compression, N5GroupMetadata, N5Metadata};
 
+mod plugin;
+pub use plugin::{N5CompressionPlugin, N5CompressionTraits};
+
 mod storage;
 pub use storage::{ImplicitGroupStoreAdapter, N5ArrayMode, N5StoreAdapter};
 
 mod convert;
 pub use convert::convert_n5;
 
+mod hierarchy;
+pub use hierarchy::{N5_VERSION, create_n5_group, create_n5_root, resize_n5_array};
+
 pub use zarrs;
 
 const N5_METADATA_KEY: &str = "attributes.json";
@@ -1,15 +1,23 @@
-use std::{borrow::Cow, num::NonZeroU64, sync::Arc};
+use std::{
+    borrow::Cow,
+    num::{NonZeroU32, NonZeroU64},
+    sync::Arc,
+};
 
 use serde::{Deserialize, Serialize};
 use zarrs::{
     array::{
         ArrayMetadataV3, BytesToBytesCodecTraits, ChunkKeyEncodingTraits, CodecMetadataOptions,
-        FillValueMetadata,
-        chunk_grid::{RegularBoundedChunkGrid, RegularBoundedChunkGridConfiguration},
+        DataType, FillValueMetadata,
+        chunk_grid::{
+            RegularBoundedChunkGrid, RegularBoundedChunkGridConfiguration, RegularChunkGrid,
+        },
         chunk_key_encoding::V2ChunkKeyEncoding,
         codec::{
-            BloscCodec, BloscCompressionLevel, BloscCompressor, BloscShuffleMode, Bz2Codec,
-            Bz2CompressionLevel, GzipCodec, ZstdCodec, api::CodecTraits,
+            BloscCodec, BloscCodecConfiguration, BloscCompressionLevel, BloscCompressor,
+            BloscShuffleMode, BytesCodec, Bz2Codec, Bz2CodecConfiguration, Bz2CompressionLevel,
+            GzipCodec, GzipCodecConfiguration, TransposeCodec, ZlibCodec, ZlibCodecConfiguration,
+            ZstdCodec, ZstdCodecConfiguration, api::CodecTraits,
         },
         data_type,
     },
@@ -18,7 +26,26 @@ use zarrs::{
     plugin::{ExtensionAliasesV3, ExtensionName, ZarrVersion},
 };
 
-use crate::{codec::N5DefaultCodec, storage::N5ArrayMode};
+#[cfg(feature = "jpeg")]
+use crate::codec::{N5JpegCodec, N5JpegCodecConfiguration};
+use crate::{
+    codec::{
+        N5DefaultCodec, N5DefaultCodecConfiguration, N5EdgeBlockPolicy, N5Lz4Codec,
+        N5Lz4CodecConfiguration, N5ObjectCodec, N5StringCodec, N5VarLengthCodec, N5XzCodec,
+        N5XzCodecConfiguration,
+    },
+    plugin::N5CompressionPlugin,
+    storage::N5ArrayMode,
+};
+
+/// Attribute in which converted Zarr metadata stashes the original N5 metadata.
+const N5_STASH_KEY: &str = "_n5";
+
+/// Key of the N5 version, present in the hierarchy root's metadata.
+const N5_VERSION_KEY: &str = "n5";
+
+/// Keys which describe the structure of an N5 array, rather than being user attributes.
+const N5_STRUCTURAL_KEYS: [&str; 4] = ["dimensions", "blockSize", "dataType", "compression"];
 
 /// Representation of N5 metadata, either an array or a group.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +95,83 @@ impl N5Metadata {
             N5Metadata::Group(m) => Ok(NodeMetadataV3::Group(m.into())),
         }
     }
+
+    /// Merge this metadata into an existing N5 metadata document, as N5 writers do.
+    ///
+    /// Keys in the existing document which are not in this metadata are retained,
+    /// including attributes written by other tools and the root's `n5` version.
+    /// The structural keys of an existing array (`dimensions`, `blockSize`, `dataType`, `compression`)
+    /// are also retained, and it is an error to try to change them,
+    /// write group metadata over them, or shadow them with user attributes.
+    pub fn merge_into(
+        self,
+        mut existing: serde_json::Map<String, serde_json::Value>,
+    ) -> crate::Result<serde_json::Map<String, serde_json::Value>> {
+        let existing_array = if N5_STRUCTURAL_KEYS.iter().any(|k| existing.contains_key(*k)) {
+            match serde_json::from_value(serde_json::Value::Object(existing.clone()))? {
+                N5Metadata::Array(m) => Some(m),
+                N5Metadata::Group(_) => {
+                    return Err(crate::Error::general(
+                        "existing N5 metadata has some, but not all, array keys",
+                    ));
+                }
+            }
+        } else {
+            None
+        };
+
+        let (n5_version, attributes) = match self {
+            N5Metadata::Group(m) => {
+                if existing_array.is_some() {
+                    return Err(crate::Error::general(
+                        "cannot overwrite N5 array metadata with group metadata",
+                    ));
+                }
+                (m.n5_version, m.attributes)
+            }
+            N5Metadata::Array(mut m) => {
+                let attributes = std::mem::take(&mut m.attributes);
+                let n5_version = m.n5_version.take();
+                if let Some(e) = &existing_array {
+                    e.check_same_structure(&m)?;
+                } else {
+                    let serde_json::Value::Object(structure) = serde_json::to_value(m)? else {
+                        unreachable!("N5 array metadata should serialize to a JSON object");
+                    };
+                    existing.extend(structure);
+                }
+                (n5_version, attributes)
+            }
+        };
+
+        if let Some(v) = n5_version {
+            existing.insert(N5_VERSION_KEY.into(), v.into());
+        }
+        for (k, v) in attributes {
+            if k == N5_VERSION_KEY || N5_STRUCTURAL_KEYS.contains(&k.as_str()) {
+                if existing.get(&k) == Some(&v) {
+                    continue;
+                }
+                return Err(crate::Error::general(format!(
+                    "attribute {k} conflicts with reserved N5 key"
+                )));
+            }
+            existing.insert(k, v);
+        }
+        Ok(existing)
+    }
+}
+
+/// Convert Zarr metadata, e.g. as written by [zarrs] through the [crate::N5StoreAdapter], into N5 metadata.
+impl TryFrom<&NodeMetadataV3> for N5Metadata {
+    type Error = crate::Error;
+
+    fn try_from(value: &NodeMetadataV3) -> Result<Self, Self::Error> {
+        match value {
+            NodeMetadataV3::Array(m) => N5ArrayMetadata::try_from(m).map(Self::Array),
+            NodeMetadataV3::Group(m) => Ok(Self::Group(m.clone().into())),
+        }
+    }
 }
 
 /// Representation of N5 group metadata.
@@ -77,7 +181,7 @@ impl N5Metadata {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct N5GroupMetadata {
     /// N5 version; present if this is a hierarchy root.
-    #[serde(rename = "n5")]
+    #[serde(rename = "n5", default, skip_serializing_if = "Option::is_none")]
     pub n5_version: Option<String>,
     /// Unstructured attributes.
     #[serde(flatten)]
@@ -92,7 +196,7 @@ pub struct N5GroupMetadata {
 #[serde(rename_all = "camelCase")]
 pub struct N5ArrayMetadata {
     /// N5 version; present if this is a hierarchy root.
-    #[serde(rename = "n5")]
+    #[serde(rename = "n5", default, skip_serializing_if = "Option::is_none")]
     pub n5_version: Option<String>,
     /// Array shape.
     pub dimensions: Vec<u64>,
@@ -110,52 +214,260 @@ pub struct N5ArrayMetadata {
 impl N5ArrayMetadata {
     /// Try to convert the N5 metadata to Zarr metadata using the given array mode.
     ///
-    /// Only the 'default' array mode is currently supported.
+    /// All array modes are supported (see [N5ArrayMode]).
+    /// Edge blocks will be truncated when written; see [Self::try_into_zarr_with_edge_block_policy].
     pub fn try_into_zarr(self, array_mode: N5ArrayMode) -> crate::Result<ArrayMetadataV3> {
+        self.try_into_zarr_with_edge_block_policy(array_mode, N5EdgeBlockPolicy::default())
+    }
+
+    /// Try to convert the N5 metadata to Zarr metadata using the given array mode,
+    /// writing edge blocks according to the given policy.
+    pub fn try_into_zarr_with_edge_block_policy(
+        self,
+        array_mode: N5ArrayMode,
+        edge_block_policy: N5EdgeBlockPolicy,
+    ) -> crate::Result<ArrayMetadataV3> {
+        self.into_zarr(array_mode, edge_block_policy, false)
+    }
+
+    /// Convert to Zarr metadata, optionally detecting each block's compression when decoding
+    /// (see [N5DefaultCodec::with_compression_detection]).
+    pub(crate) fn into_zarr(
+        self,
+        array_mode: N5ArrayMode,
+        edge_block_policy: N5EdgeBlockPolicy,
+        detect_compression: bool,
+    ) -> crate::Result<ArrayMetadataV3> {
         let ser_val = serde_json::to_value(self.clone())?;
         let mut attrs = self.attributes;
-        attrs.insert("_n5".into(), ser_val);
+        attrs.insert(N5_STASH_KEY.into(), ser_val);
 
-        let shape: Vec<_> = self.dimensions;
+        let ndim = self.dimensions.len();
+        let compression = self
+            .compression
+            .to_bytes_to_bytes_codec_for_data_type(&self.data_type)?;
 
-        let zarr_version = ZarrVersion::V3;
-        let codec_meta = match array_mode {
+        let (shape, chunk_shape, data_type, fill_value, codec_meta) = match array_mode {
             N5ArrayMode::Default => {
-                let n5_codec =
-                    N5DefaultCodec::new(self.compression.to_bytes_to_bytes_codec()?, shape.len());
-                let name = n5_codec
-                    .name(zarr_version)
-                    .unwrap_or_else(|| "zarrs.n5_default".into());
-                if let Some(config) =
-                    n5_codec.configuration(zarr_version, &CodecMetadataOptions::default())
-                {
-                    MetadataV3::new_with_configuration(name, config)
+                let n5_codec = if self.data_type == "string" {
+                    N5DefaultCodec::new_string(compression, ndim)
                 } else {
-                    MetadataV3::new(name)
+                    N5DefaultCodec::new(compression, ndim)
+                };
+                let mut n5_codec = n5_codec
+                    .with_edge_block_policy(edge_block_policy, &self.block_size)
+                    .with_compression_detection(detect_compression);
+                if let Some(concurrency) = self.compression.concurrency() {
+                    n5_codec = n5_codec.with_concurrency(concurrency);
                 }
+                (
+                    self.dimensions,
+                    self.block_size,
+                    convert_data_type(&self.data_type)?,
+                    convert_fill_value(&self.data_type),
+                    codec_metadata_v3(&n5_codec),
+                )
             }
-            _ => {
-                return Err(crate::Error::general(format!(
-                    "N5 array mode {array_mode:?} is not compatible with configured array mode {array_mode:?}"
-                )));
+            N5ArrayMode::VarLength => {
+                // each block is a single variable-length element
+                let n5_codec = N5VarLengthCodec::new(compression, &self.data_type)?;
+                let shape = std::iter::zip(&self.dimensions, &self.block_size)
+                    .map(|(d, b)| d.div_ceil(b.get()))
+                    .collect();
+                (
+                    shape,
+                    vec![NonZeroU64::MIN; ndim],
+                    data_type_metadata(&data_type::bytes()),
+                    FillValueMetadata::Array(Vec::default()),
+                    codec_metadata_v3(&n5_codec),
+                )
+            }
+            N5ArrayMode::Object => {
+                // each block is a single opaque payload
+                let n5_codec = N5ObjectCodec::new(compression);
+                let shape = std::iter::zip(&self.dimensions, &self.block_size)
+                    .map(|(d, b)| d.div_ceil(b.get()))
+                    .collect();
+                (
+                    shape,
+                    vec![NonZeroU64::MIN; ndim],
+                    data_type_metadata(&data_type::bytes()),
+                    FillValueMetadata::Array(Vec::default()),
+                    codec_metadata_v3(&n5_codec),
+                )
             }
         };
-
-        let chunk_grid = convert_chunk_grid(&self.block_size)?;
-        let data_type = convert_data_type(&self.data_type)?;
-        let fill_value = convert_fill_value();
+        let chunk_grid = convert_chunk_grid(&chunk_shape)?;
 
         let out = ArrayMetadataV3::new(shape, chunk_grid, data_type, fill_value, vec![codec_meta])
             .with_chunk_key_encoding(convert_chunk_key_encoding())
             .with_attributes(attrs);
         Ok(out)
     }
+
+    /// Check that two arrays' structural keys are equivalent.
+    ///
+    /// Compressions are equivalent if they are equal or produce the same Zarr codec.
+    fn check_same_structure(&self, other: &Self) -> crate::Result<()> {
+        let conflict = |key: &str, a: &dyn std::fmt::Debug, b: &dyn std::fmt::Debug| {
+            Err(crate::Error::general(format!(
+                "refusing to change {key} of existing N5 array from {a:?} to {b:?}"
+            )))
+        };
+        if self.dimensions != other.dimensions {
+            return conflict("dimensions", &self.dimensions, &other.dimensions);
+        }
+        if self.block_size != other.block_size {
+            return conflict("blockSize", &self.block_size, &other.block_size);
+        }
+        if self.data_type != other.data_type {
+            return conflict("dataType", &self.data_type, &other.data_type);
+        }
+        if self.compression != other.compression
+            && self.compression.codec_metadata(&self.data_type)
+                != other.compression.codec_metadata(&self.data_type)
+        {
+            return conflict("compression", &self.compression, &other.compression);
+        }
+        Ok(())
+    }
+}
+
+/// Convert Zarr array metadata into N5 array metadata; the reverse of [N5ArrayMetadata::try_into_zarr].
+///
+/// The array must use a single [N5DefaultCodec] and be otherwise representable in N5:
+/// a regular (bounded) chunk grid, an N5 data type, the `v2` chunk key encoding with a `/` separator, and a fill value of 0 (or the empty string, for strings).
+/// Arrays using an [N5VarLengthCodec] or [N5ObjectCodec] are instead restored from the `_n5` attribute, which must be present,
+/// with the Zarr attributes, and with N5 dimensions of whole blocks wherever the Zarr shape (of one element per block) has changed.
+/// If the Zarr metadata was converted from N5 metadata,
+/// details which Zarr cannot represent (the N5 version, and the exact compression parameters) are restored from the `_n5` attribute.
+/// Otherwise, the compression must be encodable (see [N5Compression::can_encode]),
+/// so that arrays which could never be written are not created.
+impl TryFrom<&ArrayMetadataV3> for N5ArrayMetadata {
+    type Error = crate::Error;
+
+    fn try_from(metadata: &ArrayMetadataV3) -> Result<Self, Self::Error> {
+        if !metadata.storage_transformers.is_empty() {
+            return Err(crate::Error::general(
+                "storage transformers cannot be represented in N5",
+            ));
+        }
+        if metadata.dimension_names.is_some() {
+            log::warn!("dimension names cannot be represented in N5 and will be dropped");
+        }
+        if metadata.chunk_key_encoding != convert_chunk_key_encoding() {
+            return Err(crate::Error::general(format!(
+                "N5 arrays must use the v2 chunk key encoding with '/' separator, got {}",
+                metadata.chunk_key_encoding
+            )));
+        }
+        if let [codec] = metadata.codecs.as_slice()
+            && (N5VarLengthCodec::matches_name_v3(codec.name())
+                || N5ObjectCodec::matches_name_v3(codec.name()))
+        {
+            // the Zarr array has an element per block, so the rest of the N5 structure is only in the stash
+            let mut attributes = metadata.attributes.clone();
+            let Some(N5Metadata::Array(mut stashed)) = attributes
+                .remove(N5_STASH_KEY)
+                .and_then(|v| serde_json::from_value::<N5Metadata>(v).ok())
+            else {
+                return Err(crate::Error::general(
+                    "N5 varlength and object arrays can only be converted from their stashed N5 metadata",
+                ));
+            };
+            if reverse_chunk_grid(&metadata.chunk_grid)?
+                .iter()
+                .any(|n| n.get() != 1)
+            {
+                return Err(crate::Error::general(
+                    "N5 varlength and object arrays must have a chunk shape of 1 in every dimension",
+                ));
+            }
+            if metadata.shape.len() != stashed.dimensions.len() {
+                return Err(crate::Error::general(format!(
+                    "shape {:?} does not match the dimensionality of the stashed N5 dimensions {:?}",
+                    metadata.shape, stashed.dimensions
+                )));
+            }
+            // keep the stashed dimensions unless the number of blocks has changed (e.g. the array was resized)
+            for ((dimension, block_size), num_blocks) in stashed
+                .dimensions
+                .iter_mut()
+                .zip(&stashed.block_size)
+                .zip(&metadata.shape)
+            {
+                if dimension.div_ceil(block_size.get()) != *num_blocks {
+                    *dimension = num_blocks * block_size.get();
+                }
+            }
+            stashed.attributes = attributes;
+            return Ok(stashed);
+        }
+        reverse_fill_value(&metadata.fill_value)?;
+
+        let [codec] = metadata.codecs.as_slice() else {
+            return Err(crate::Error::general(
+                "N5 arrays must have a single N5 default codec",
+            ));
+        };
+        if !N5DefaultCodec::matches_name_v3(codec.name()) {
+            return Err(crate::Error::general(format!(
+                "N5 arrays must have a single N5 default codec, got {}",
+                codec.name()
+            )));
+        }
+        let configuration: N5DefaultCodecConfiguration =
+            codec.to_typed_configuration().map_err(|e| {
+                crate::Error::general(format!("invalid N5 default codec configuration: {e}"))
+            })?;
+        let mut compression = reverse_codecs(configuration.codecs())?;
+        if let (N5Compression::Blosc { nthreads, .. }, Some(concurrency)) =
+            (&mut compression, configuration.concurrency())
+        {
+            *nthreads = concurrency.get();
+        }
+
+        let mut attributes = metadata.attributes.clone();
+        let stashed = attributes
+            .remove(N5_STASH_KEY)
+            .and_then(|v| serde_json::from_value::<N5Metadata>(v).ok());
+
+        let mut out = Self {
+            n5_version: None,
+            dimensions: metadata.shape.clone(),
+            block_size: reverse_chunk_grid(&metadata.chunk_grid)?,
+            data_type: reverse_data_type(&metadata.data_type)?,
+            compression,
+            attributes,
+        };
+        let mut existing_compression = false;
+        match stashed {
+            Some(N5Metadata::Array(stashed)) => {
+                out.n5_version = stashed.n5_version;
+                if stashed.compression.codec_metadata(&out.data_type)
+                    == out.compression.codec_metadata(&out.data_type)
+                {
+                    out.compression = stashed.compression;
+                    existing_compression = true;
+                }
+            }
+            Some(N5Metadata::Group(stashed)) => out.n5_version = stashed.n5_version,
+            None => (),
+        }
+        if !existing_compression && !out.compression.can_encode() {
+            return Err(crate::Error::general(format!(
+                "N5 compression {:?} is decode-only, so cannot be used for new arrays",
+                out.compression
+            )));
+        }
+        Ok(out)
+    }
 }
 
 /// N5 block compression configuration.
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
-#[serde(rename_all = "camelCase", tag = "type")]
+#[serde(remote = "Self", rename_all = "camelCase", tag = "type")]
 pub enum N5Compression {
     /// Uncompressed.
     #[default]
@@ -169,6 +481,10 @@ pub enum N5Compression {
         /// Default -1, meaning "implementation default" (usually 6).
         #[serde(default = "default_gzip_level")]
         level: i8,
+        /// Whether blocks are zlib (RFC 1950) rather than gzip (RFC 1952) streams.
+        /// Default false.
+        #[serde(rename = "useZlib", default)]
+        use_zlib: bool,
     },
     Lz4 {
         /// Default 65536. Must be a positive integer.
@@ -185,6 +501,17 @@ pub enum N5Compression {
         /// Default 3. Must be in the range -5..=22.
         #[serde(default = "default_zstd_level")]
         level: i32,
+        /// Whether to write a checksum in each frame. Default false.
+        ///
+        /// Checksums are always verified when present, regardless of this setting.
+        #[serde(rename = "useChecksums", default)]
+        use_checksums: bool,
+        /// Number of threads the Java compressor uses for each block. Default 0 (single-threaded).
+        ///
+        /// This is preserved in the metadata, but not used when encoding:
+        /// zarrs parallelises over blocks instead.
+        #[serde(rename = "nbWorkers", default)]
+        nb_workers: u32,
     },
     /// <https://github.com/saalfeldlab/n5-blosc>
     Blosc {
@@ -207,6 +534,48 @@ pub enum N5Compression {
         #[serde(default = "default_blosc_nthreads")]
         nthreads: u32,
     },
+    /// <https://github.com/saalfeldlab/n5-jpeg>
+    ///
+    /// Only for `uint8` data, and only supported for reading with the `jpeg` feature,
+    /// so arrays with this compression cannot be created.
+    Jpeg {
+        /// Must be in the range 0..=100.
+        quality: u8,
+    },
+    /// Any other compression object, including its `type`,
+    /// which is handled by the [N5CompressionPlugin] registered for that `type`.
+    #[serde(skip)]
+    Plugin(serde_json::Map<String, serde_json::Value>),
+}
+
+/// The `type`s of the built-in [N5Compression] variants.
+pub(crate) const BUILT_IN_COMPRESSION_TYPES: &[&str] =
+    &["raw", "bzip2", "gzip", "lz4", "xz", "zstd", "blosc", "jpeg"];
+
+impl Serialize for N5Compression {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            N5Compression::Plugin(compression) => compression.serialize(serializer),
+            _ => N5Compression::serialize(self, serializer),
+        }
+    }
+}
+
+/// Dispatches on the `type` first, so that invalid built-in compression objects are errors
+/// rather than falling through to [N5Compression::Plugin].
+impl<'de> Deserialize<'de> for N5Compression {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let compression = serde_json::Map::deserialize(deserializer)?;
+        match compression.get("type").and_then(|t| t.as_str()) {
+            Some(t) if BUILT_IN_COMPRESSION_TYPES.contains(&t) => {
+                N5Compression::deserialize(serde_json::Value::Object(compression))
+                    .map_err(D::Error::custom)
+            }
+            Some(_) => Ok(N5Compression::Plugin(compression)),
+            None => Err(D::Error::missing_field("type")),
+        }
+    }
 }
 
 fn default_blosc_cname() -> BloscCompressor {
@@ -247,8 +616,54 @@ fn default_xz_preset() -> u32 {
 
 impl N5Compression {
     /// Convert to a bytes-to-bytes codec if possible.
+    ///
+    /// Blosc's `typesize` is only set if it is given in the metadata;
+    /// prefer [N5Compression::to_bytes_to_bytes_codec_for_data_type] when the data type is known.
     pub fn to_bytes_to_bytes_codec(
         &self,
+    ) -> crate::Result<Option<Arc<dyn BytesToBytesCodecTraits>>> {
+        self.bytes_to_bytes_codec(None)
+    }
+
+    /// Convert to a bytes-to-bytes codec for blocks of the given N5 `dataType`, if possible.
+    ///
+    /// Blosc's `typesize` defaults to the width of the data type,
+    /// so that shuffling (including the `-1` automatic mode) works on whole elements.
+    pub fn to_bytes_to_bytes_codec_for_data_type(
+        &self,
+        data_type: &str,
+    ) -> crate::Result<Option<Arc<dyn BytesToBytesCodecTraits>>> {
+        self.bytes_to_bytes_codec(Some(data_type))
+    }
+
+    /// Whether blocks can be written with this compression, rather than only read.
+    ///
+    /// JPEG, and compressions from plugins which are not registered or are decode-only, cannot be written.
+    pub fn can_encode(&self) -> bool {
+        match self {
+            N5Compression::Jpeg { .. } => false,
+            N5Compression::Plugin(compression) => compression
+                .get("type")
+                .and_then(|t| t.as_str())
+                .and_then(N5CompressionPlugin::find)
+                .is_some_and(N5CompressionPlugin::can_encode),
+            _ => true,
+        }
+    }
+
+    /// The number of threads the compression may use for each block, if more than one.
+    ///
+    /// This is Blosc's `nthreads`.
+    pub fn concurrency(&self) -> Option<NonZeroU32> {
+        match self {
+            N5Compression::Blosc { nthreads, .. } if *nthreads > 1 => NonZeroU32::new(*nthreads),
+            _ => None,
+        }
+    }
+
+    fn bytes_to_bytes_codec(
+        &self,
+        data_type: Option<&str>,
     ) -> crate::Result<Option<Arc<dyn BytesToBytesCodecTraits>>> {
         let b2b: Arc<dyn BytesToBytesCodecTraits> = match self {
             N5Compression::Raw => return Ok(None),
@@ -256,7 +671,7 @@ impl N5Compression {
                 Bz2CompressionLevel::new(*block_size as u32)
                     .map_err(|n| crate::Error::general(format!("invalid bz2 block size {n}")))?,
             )),
-            N5Compression::Gzip { level } => {
+            N5Compression::Gzip { level, use_zlib } => {
                 let lvl_int: u32 = match level {
                     -1 => 6,
                     n if *n >= 0 => *n as u32,
@@ -266,12 +681,19 @@ impl N5Compression {
                         )));
                     }
                 };
-                Arc::new(GzipCodec::new(lvl_int).map_err(crate::Error::wrap)?)
-            }
-            N5Compression::Zstd { level } => {
-                // TODO: checksum?
-                Arc::new(ZstdCodec::new(*level, false))
+                if *use_zlib {
+                    Arc::new(ZlibCodec::new(lvl_int.try_into().map_err(|n| {
+                        crate::Error::general(format!("invalid zlib compression level {n}"))
+                    })?))
+                } else {
+                    Arc::new(GzipCodec::new(lvl_int).map_err(crate::Error::wrap)?)
+                }
             }
+            N5Compression::Zstd {
+                level,
+                use_checksums,
+                ..
+            } => Arc::new(ZstdCodec::new(*level, *use_checksums)),
             N5Compression::Blosc {
                 cname,
                 clevel,
@@ -280,6 +702,7 @@ impl N5Compression {
                 typesize,
                 ..
             } => {
+                let typesize = typesize.or_else(|| data_type.and_then(data_type_size));
                 let shuffle_mode = match *shuffle {
                     -1 => {
                         if typesize.unwrap_or(1) > 1 {
@@ -298,23 +721,150 @@ impl N5Compression {
                     }
                 };
                 Arc::new(
-                    BloscCodec::new(*cname, *clevel, *blocksize, shuffle_mode, *typesize).map_err(
+                    BloscCodec::new(*cname, *clevel, *blocksize, shuffle_mode, typesize).map_err(
                         |e| crate::Error::general(format!("invalid Blosc configuration: {e}")),
                     )?,
                 )
             }
-            // N5Compression::Lz4 { level } => todo!(),
-            // N5Compression::Xz { preset } => todo!(),
-            c => {
-                return Err(crate::Error::general(format!(
-                    "unsupported N5 compression: {c:?}"
-                )));
+            N5Compression::Lz4 { level } => {
+                Arc::new(N5Lz4Codec::new(u32::try_from(*level).map_err(|_| {
+                    crate::Error::general(format!("invalid LZ4 block size {level}"))
+                })?)?)
+            }
+            N5Compression::Xz { preset } => Arc::new(N5XzCodec::new(*preset)?),
+            #[cfg(feature = "jpeg")]
+            N5Compression::Jpeg { quality } => {
+                if let Some(dt) = data_type.filter(|dt| *dt != "uint8") {
+                    return Err(crate::Error::general(format!(
+                        "JPEG compression requires uint8 data, got {dt}"
+                    )));
+                }
+                Arc::new(N5JpegCodec::new(*quality)?)
+            }
+            #[cfg(not(feature = "jpeg"))]
+            N5Compression::Jpeg { .. } => {
+                return Err(crate::Error::general(
+                    "JPEG compression requires the `jpeg` feature",
+                ));
+            }
+            N5Compression::Plugin(compression) => {
+                let Some(compression_type) = compression.get("type").and_then(|t| t.as_str())
+                else {
+                    return Err(crate::Error::general("N5 compression has no type"));
+                };
+                let Some(plugin) = N5CompressionPlugin::find(compression_type) else {
+                    return Err(crate::Error::general(format!(
+                        "unsupported or invalid N5 compression {compression_type}"
+                    )));
+                };
+                plugin.create(compression)?
             }
         };
         Ok(Some(b2b))
     }
 }
 
+impl N5Compression {
+    /// Metadata for the equivalent Zarr codec for the given N5 `dataType`, if there is one.
+    fn codec_metadata(&self, data_type: &str) -> Option<MetadataV3> {
+        let codec = self.bytes_to_bytes_codec(Some(data_type)).ok()??;
+        let name = codec.name(ZarrVersion::V3)?;
+        let metadata = match codec.configuration(ZarrVersion::V3, &CodecMetadataOptions::default())
+        {
+            Some(config) => MetadataV3::new_with_configuration(name, config),
+            None => MetadataV3::new(name),
+        };
+        Some(metadata)
+    }
+
+    /// Try to represent a bytes-to-bytes codec's metadata as N5 compression.
+    fn try_from_codec_metadata(metadata: &MetadataV3) -> crate::Result<Self> {
+        let name = metadata.name();
+        let invalid = |e| crate::Error::general(format!("invalid {name} configuration: {e}"));
+        #[cfg(feature = "jpeg")]
+        if N5JpegCodec::matches_name_v3(name) {
+            let c: N5JpegCodecConfiguration = metadata.to_typed_configuration().map_err(invalid)?;
+            return Ok(N5Compression::Jpeg { quality: c.quality });
+        }
+        let out = if GzipCodec::matches_name_v3(name) {
+            let GzipCodecConfiguration::V1(c) =
+                metadata.to_typed_configuration().map_err(invalid)?
+            else {
+                return Err(crate::Error::general("unsupported gzip configuration"));
+            };
+            N5Compression::Gzip {
+                level: c.level.as_u32() as i8,
+                use_zlib: false,
+            }
+        } else if ZlibCodec::matches_name_v3(name) {
+            let ZlibCodecConfiguration::V1(c) =
+                metadata.to_typed_configuration().map_err(invalid)?
+            else {
+                return Err(crate::Error::general("unsupported zlib configuration"));
+            };
+            N5Compression::Gzip {
+                level: c.level.as_u32() as i8,
+                use_zlib: true,
+            }
+        } else if Bz2Codec::matches_name_v3(name) {
+            let Bz2CodecConfiguration::V1(c) =
+                metadata.to_typed_configuration().map_err(invalid)?
+            else {
+                return Err(crate::Error::general("unsupported bz2 configuration"));
+            };
+            N5Compression::Bzip2 {
+                block_size: c.level.as_u32() as u8,
+            }
+        } else if ZstdCodec::matches_name_v3(name) {
+            let (level, checksum) = match metadata.to_typed_configuration().map_err(invalid)? {
+                ZstdCodecConfiguration::V1(c) => (c.level, c.checksum),
+                ZstdCodecConfiguration::Numcodecs(c) => (c.level, false),
+                _ => return Err(crate::Error::general("unsupported zstd configuration")),
+            };
+            N5Compression::Zstd {
+                level: level.into(),
+                use_checksums: checksum,
+                nb_workers: 0,
+            }
+        } else if BloscCodec::matches_name_v3(name) {
+            let BloscCodecConfiguration::V1(c) =
+                metadata.to_typed_configuration().map_err(invalid)?
+            else {
+                return Err(crate::Error::general("unsupported blosc configuration"));
+            };
+            N5Compression::Blosc {
+                cname: c.cname,
+                clevel: c.clevel,
+                shuffle: match c.shuffle {
+                    BloscShuffleMode::NoShuffle => 0,
+                    BloscShuffleMode::Shuffle => 1,
+                    BloscShuffleMode::BitShuffle => 2,
+                },
+                blocksize: Some(c.blocksize),
+                typesize: c.typesize,
+                nthreads: default_blosc_nthreads(),
+            }
+        } else if N5Lz4Codec::matches_name_v3(name) {
+            let c: N5Lz4CodecConfiguration = metadata.to_typed_configuration().map_err(invalid)?;
+            N5Compression::Lz4 {
+                level: c.block_size.into(),
+            }
+        } else if N5XzCodec::matches_name_v3(name) {
+            let c: N5XzCodecConfiguration = metadata.to_typed_configuration().map_err(invalid)?;
+            N5Compression::Xz { preset: c.preset }
+        } else if let Some(compression) =
+            N5CompressionPlugin::compression_from_codec_metadata(metadata)
+        {
+            N5Compression::Plugin(compression)
+        } else {
+            return Err(crate::Error::general(format!(
+                "codec {name} cannot be represented as N5 compression"
+            )));
+        };
+        Ok(out)
+    }
+}
+
 fn convert_chunk_grid(block_size: &[NonZeroU64]) -> crate::Result<MetadataV3> {
     let chunk_shape: Vec<_> = block_size.to_vec();
     let out = MetadataV3::new_with_serializable_configuration(
@@ -328,6 +878,18 @@ fn convert_chunk_grid(block_size: &[NonZeroU64]) -> crate::Result<MetadataV3> {
     Ok(out)
 }
 
+/// Width in bytes of an N5 `dataType`, if it is a known fixed-width type.
+pub(crate) fn data_type_size(data_type: &str) -> Option<usize> {
+    let size = match data_type {
+        "uint8" | "int8" => 1,
+        "uint16" | "int16" => 2,
+        "uint32" | "int32" | "float32" => 4,
+        "uint64" | "int64" | "float64" => 8,
+        _ => return None,
+    };
+    Some(size)
+}
+
 fn convert_data_type(data_type: &str) -> crate::Result<MetadataV3> {
     let data_type = match data_type {
         "uint8" => data_type::uint8(),
@@ -340,22 +902,101 @@ fn convert_data_type(data_type: &str) -> crate::Result<MetadataV3> {
         "uint64" => data_type::uint64(),
         "float32" => data_type::float32(),
         "float64" => data_type::float64(),
+        "string" => data_type::string(),
         s => return Err(crate::Error::general(format!("unsupported data type: {s}"))),
     };
+    Ok(data_type_metadata(&data_type))
+}
+
+fn data_type_metadata(data_type: &DataType) -> MetadataV3 {
     let data_type_name = data_type
         .name_v3()
         .map_or_else(String::new, Cow::into_owned);
     let data_type_configuration = data_type.configuration_v3();
-    let out = if data_type_configuration.is_empty() {
+    if data_type_configuration.is_empty() {
         MetadataV3::new(data_type_name)
     } else {
         MetadataV3::new_with_configuration(data_type_name, data_type_configuration)
+    }
+}
+
+/// Metadata for one of this crate's array-to-bytes codecs.
+fn codec_metadata_v3(codec: &dyn CodecTraits) -> MetadataV3 {
+    let zarr_version = ZarrVersion::V3;
+    let name = codec
+        .name(zarr_version)
+        .expect("N5 codecs should have a Zarr v3 name");
+    match codec.configuration(zarr_version, &CodecMetadataOptions::default()) {
+        Some(config) => MetadataV3::new_with_configuration(name, config),
+        None => MetadataV3::new(name),
+    }
+}
+
+fn reverse_chunk_grid(chunk_grid: &MetadataV3) -> crate::Result<Vec<NonZeroU64>> {
+    let name = chunk_grid.name();
+    if !(RegularBoundedChunkGrid::matches_name_v3(name) || RegularChunkGrid::matches_name_v3(name))
+    {
+        return Err(crate::Error::general(format!(
+            "chunk grid {name} cannot be represented in N5"
+        )));
+    }
+    let configuration: RegularBoundedChunkGridConfiguration =
+        chunk_grid
+            .to_typed_configuration()
+            .map_err(|e| crate::Error::general(format!("invalid {name} configuration: {e}")))?;
+    Ok(configuration.chunk_shape)
+}
+
+fn reverse_data_type(data_type: &MetadataV3) -> crate::Result<String> {
+    let name = data_type.name();
+    match name {
+        "uint8" | "int8" | "int16" | "uint16" | "int32" | "uint32" | "int64" | "uint64"
+        | "float32" | "float64" | "string"
+            if data_type.configuration_is_none_or_empty() =>
+        {
+            Ok(name.to_string())
+        }
+        _ => Err(crate::Error::general(format!(
+            "data type {data_type} cannot be represented in N5"
+        ))),
+    }
+}
+
+/// Missing N5 blocks are read as zeros (or empty strings), so that is the only representable fill value.
+fn reverse_fill_value(fill_value: &FillValueMetadata) -> crate::Result<()> {
+    match fill_value {
+        FillValueMetadata::Number(n) if n.as_f64() == Some(0.0) => Ok(()),
+        FillValueMetadata::String(s) if s.is_empty() => Ok(()),
+        _ => Err(crate::Error::general(format!(
+            "fill value {fill_value} cannot be represented in N5, which always uses 0"
+        ))),
+    }
+}
+
+/// Find the N5 compression from the codecs inside an [N5DefaultCodec].
+fn reverse_codecs(codecs: &[MetadataV3]) -> crate::Result<N5Compression> {
+    let mut compressions = codecs.iter().filter(|c| {
+        !(TransposeCodec::matches_name_v3(c.name())
+            || BytesCodec::matches_name_v3(c.name())
+            || N5StringCodec::matches_name_v3(c.name()))
+    });
+    let Some(compression) = compressions.next() else {
+        return Ok(N5Compression::Raw);
     };
-    Ok(out)
+    if compressions.next().is_some() {
+        return Err(crate::Error::general(
+            "N5 blocks can have at most one compression codec",
+        ));
+    }
+    N5Compression::try_from_codec_metadata(compression)
 }
 
-fn convert_fill_value() -> FillValueMetadata {
-    FillValueMetadata::Number(serde_json::Number::from(0))
+fn convert_fill_value(data_type: &str) -> FillValueMetadata {
+    if data_type == "string" {
+        FillValueMetadata::String(String::new())
+    } else {
+        FillValueMetadata::Number(serde_json::Number::from(0))
+    }
 }
 
 fn convert_chunk_key_encoding() -> MetadataV3 {
@@ -372,7 +1013,22 @@ impl From<N5GroupMetadata> for GroupMetadataV3 {
         let ser_val =
             serde_json::to_value(value.clone()).expect("N5 group metadata should be serializable");
         let mut attrs = value.attributes;
-        attrs.insert("_n5".into(), ser_val);
+        attrs.insert(N5_STASH_KEY.into(), ser_val);
         Self::default().with_attributes(attrs)
     }
 }
+
+/// The N5 version is restored from the `_n5` attribute, if present.
+impl From<GroupMetadataV3> for N5GroupMetadata {
+    fn from(value: GroupMetadataV3) -> Self {
+        let mut attributes = value.attributes;
+        let n5_version = attributes
+            .remove(N5_STASH_KEY)
+            .and_then(|v| serde_json::from_value::<N5Metadata>(v).ok())
+            .and_then(|m| m.version().map(str::to_string));
+        Self {
+            n5_version,
+            attributes,
+        }
+    }
+}
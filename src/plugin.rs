@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use zarrs::array::BytesToBytesCodecTraits;
+use zarrs::metadata::v3::MetadataV3;
+
+use crate::metadata::BUILT_IN_COMPRESSION_TYPES;
+
+/// An N5 compression object, including its `type`.
+type CompressionObject = serde_json::Map<String, serde_json::Value>;
+
+/// A third-party N5 compression, registered with [inventory] through an [N5CompressionPlugin].
+///
+/// The codec it creates is stored in converted Zarr metadata,
+/// so it should also be registered as a [zarrs::array::codec::api::CodecPluginV3].
+pub trait N5CompressionTraits {
+    /// The compression's `type` in N5 metadata.
+    ///
+    /// Plugins with the `type` of a built-in [crate::N5Compression] variant (e.g. `gzip`) are ignored.
+    const TYPE: &'static str;
+
+    /// Create a codec from the N5 compression object, including its `type`.
+    fn create(compression: &CompressionObject) -> crate::Result<Arc<dyn BytesToBytesCodecTraits>>;
+
+    /// Recover the N5 compression object from the metadata of a codec created by [N5CompressionTraits::create].
+    ///
+    /// This is only needed when the original N5 metadata is not stashed in the Zarr metadata
+    /// (e.g. for arrays created through the Zarr API).
+    /// The `type` is added if it is missing.
+    fn try_from_codec_metadata(_metadata: &MetadataV3) -> Option<CompressionObject> {
+        None
+    }
+
+    /// Whether the codec created by [N5CompressionTraits::create] can encode blocks, as well as decode them.
+    ///
+    /// Arrays with a decode-only compression cannot be created.
+    fn can_encode() -> bool {
+        true
+    }
+}
+
+/// Registration of an [N5CompressionTraits] implementation.
+///
+/// N5 compression objects whose `type` is not built in are handled by the first plugin with that `type`.
+///
+/// ```ignore
+/// inventory::submit! {
+///     zarrs_n5::N5CompressionPlugin::new::<MyCompression>()
+/// }
+/// ```
+pub struct N5CompressionPlugin {
+    compression_type: &'static str,
+    create: fn(&CompressionObject) -> crate::Result<Arc<dyn BytesToBytesCodecTraits>>,
+    try_from_codec_metadata: fn(&MetadataV3) -> Option<CompressionObject>,
+    can_encode: fn() -> bool,
+}
+
+inventory::collect!(N5CompressionPlugin);
+
+impl N5CompressionPlugin {
+    pub const fn new<T: N5CompressionTraits>() -> Self {
+        Self {
+            compression_type: T::TYPE,
+            create: T::create,
+            try_from_codec_metadata: T::try_from_codec_metadata,
+            can_encode: T::can_encode,
+        }
+    }
+
+    pub fn compression_type(&self) -> &'static str {
+        self.compression_type
+    }
+
+    /// The registered plugins, except those with a built-in compression `type`.
+    fn registered() -> impl Iterator<Item = &'static Self> {
+        inventory::iter::<Self>
+            .into_iter()
+            .filter(|p| !BUILT_IN_COMPRESSION_TYPES.contains(&p.compression_type))
+    }
+
+    /// The registered plugin for the given N5 compression `type`, if any.
+    pub(crate) fn find(compression_type: &str) -> Option<&'static Self> {
+        Self::registered().find(|p| p.compression_type == compression_type)
+    }
+
+    pub(crate) fn create(
+        &self,
+        compression: &CompressionObject,
+    ) -> crate::Result<Arc<dyn BytesToBytesCodecTraits>> {
+        (self.create)(compression)
+    }
+
+    pub(crate) fn can_encode(&self) -> bool {
+        (self.can_encode)()
+    }
+
+    /// Try each registered plugin in turn to recover an N5 compression object from codec metadata.
+    pub(crate) fn compression_from_codec_metadata(
+        metadata: &MetadataV3,
+    ) -> Option<CompressionObject> {
+        Self::registered().find_map(|p| {
+            let mut compression = (p.try_from_codec_metadata)(metadata)?;
+            compression
+                .entry("type")
+                .or_insert_with(|| p.compression_type.into());
+            Some(compression)
+        })
+    }
+}
+
+impl std::fmt::Debug for N5CompressionPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("N5CompressionPlugin")
+            .field("compression_type", &self.compression_type)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,11 +1,15 @@
+use bytes::Bytes;
 use zarrs::storage::{
     AsyncListableStorageTraits, AsyncMaybeBytesIterator, AsyncReadableListableStorageTraits,
-    AsyncReadableStorageTraits, MaybeBytes, StorageError, StoreKey, StoreKeys, StoreKeysPrefixes,
-    StorePrefix,
-    byte_range::{ByteRange, ByteRangeIterator},
+    AsyncReadableStorageTraits, AsyncWritableStorageTraits, MaybeBytes, OffsetBytesIterator,
+    StorageError, StoreKey, StoreKeys, StoreKeysPrefixes, StorePrefix,
+    byte_range::ByteRangeIterator,
 };
 
-use super::{ImplicitGroupStoreAdapter, N5StoreAdapter};
+use super::{
+    BLOCK_MODE_RANGE, ImplicitGroupStoreAdapter, N5StoreAdapter, array_block_keys, block_mode,
+    filter_chunk_keys, is_zarr_json,
+};
 
 #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
@@ -18,24 +22,17 @@ impl<S: AsyncReadableStorageTraits> AsyncReadableStorageTraits for N5StoreAdapte
         }
     }
 
-    async fn get_partial(
-        &self,
-        _key: &StoreKey,
-        _byte_range: ByteRange,
-    ) -> Result<MaybeBytes, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial not supported".into(),
-        ))
-    }
-
     async fn get_partial_many<'a>(
         &'a self,
-        _key: &StoreKey,
-        _byte_ranges: ByteRangeIterator<'a>,
+        key: &StoreKey,
+        byte_ranges: ByteRangeIterator<'a>,
     ) -> Result<AsyncMaybeBytesIterator<'a>, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial_many not supported".into(),
-        ))
+        if self.intercept_zarr_json(key).is_some() {
+            return Err(StorageError::Unsupported(
+                "partial reads of Zarr metadata not supported".into(),
+            ));
+        }
+        self.inner.get_partial_many(key, byte_ranges).await
     }
 
     async fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
@@ -47,7 +44,7 @@ impl<S: AsyncReadableStorageTraits> AsyncReadableStorageTraits for N5StoreAdapte
     }
 
     fn supports_get_partial(&self) -> bool {
-        false
+        self.inner.supports_get_partial()
     }
 }
 
@@ -75,18 +72,71 @@ impl<S: AsyncListableStorageTraits> AsyncListableStorageTraits for N5StoreAdapte
     }
 }
 
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl<S: AsyncReadableStorageTraits + AsyncWritableStorageTraits + AsyncListableStorageTraits>
+    AsyncWritableStorageTraits for N5StoreAdapter<S>
+{
+    async fn set(&self, key: &StoreKey, value: Bytes) -> Result<(), StorageError> {
+        if let Some(meta_key) = self.intercept_zarr_json(key) {
+            let existing = self.inner.get(&meta_key).await?;
+            let n5_value = self.convert_zarr_metadata(&meta_key, &value, existing)?;
+            self.inner.set(&meta_key, n5_value).await
+        } else {
+            self.inner.set(key, value).await
+        }
+    }
+
+    async fn set_partial_many<'a>(
+        &'a self,
+        key: &StoreKey,
+        offset_values: OffsetBytesIterator<'a>,
+    ) -> Result<(), StorageError> {
+        if self.intercept_zarr_json(key).is_some() {
+            return Err(StorageError::Unsupported(
+                "partial writes of Zarr metadata not supported".into(),
+            ));
+        }
+        self.inner.set_partial_many(key, offset_values).await
+    }
+
+    async fn erase(&self, key: &StoreKey) -> Result<(), StorageError> {
+        if let Some(meta_key) = self.intercept_zarr_json(key) {
+            if let Some((prefix, ndim)) =
+                self.erased_array(&meta_key, self.inner.get(&meta_key).await?)?
+            {
+                let keys = array_block_keys(&prefix, self.inner.list_prefix(&prefix).await?, ndim);
+                self.inner.erase_many(&keys).await?;
+            }
+            self.inner.erase(&meta_key).await
+        } else {
+            self.inner.erase(key).await
+        }
+    }
+
+    async fn erase_prefix(&self, prefix: &StorePrefix) -> Result<(), StorageError> {
+        self.inner.erase_prefix(prefix).await
+    }
+
+    fn supports_set_partial(&self) -> bool {
+        self.inner.supports_set_partial()
+    }
+}
+
 #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<S: AsyncReadableListableStorageTraits> N5StoreAdapter<S> {
+    /// Infer the array mode from the header of the array's first block.
+    ///
+    /// See [N5StoreAdapter::infer_array_mode].
     pub async fn async_infer_array_mode(
         &self,
         prefix: &StorePrefix,
     ) -> Result<Option<super::N5ArrayMode>, StorageError> {
-        // TODO: could parallelise
-        for key in self.filter_chunk_keys(prefix, self.list_prefix(prefix).await?) {
-            let Some(mode) = self.block_mode(self.get(&key).await?) else {
+        for key in filter_chunk_keys(prefix, self.list_prefix(prefix).await?) {
+            let Some(mode) = block_mode(self.get_partial(&key, BLOCK_MODE_RANGE).await?) else {
                 continue;
             };
-            return Ok(Some(mode.into()));
+            return Ok(Some(mode));
         }
         Ok(None)
     }
@@ -100,24 +150,17 @@ impl<S: AsyncReadableStorageTraits> AsyncReadableStorageTraits for ImplicitGroup
         Ok(self.maybe_infer_metadata(key, value))
     }
 
-    async fn get_partial(
-        &self,
-        _key: &StoreKey,
-        _byte_range: ByteRange,
-    ) -> Result<MaybeBytes, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial not supported".into(),
-        ))
-    }
-
     async fn get_partial_many<'a>(
         &'a self,
-        _key: &StoreKey,
-        _byte_ranges: ByteRangeIterator<'a>,
+        key: &StoreKey,
+        byte_ranges: ByteRangeIterator<'a>,
     ) -> Result<AsyncMaybeBytesIterator<'a>, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial_many not supported".into(),
-        ))
+        if is_zarr_json(key) {
+            return Err(StorageError::Unsupported(
+                "partial reads of Zarr metadata not supported".into(),
+            ));
+        }
+        self.inner.get_partial_many(key, byte_ranges).await
     }
 
     async fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
@@ -125,7 +168,7 @@ impl<S: AsyncReadableStorageTraits> AsyncReadableStorageTraits for ImplicitGroup
     }
 
     fn supports_get_partial(&self) -> bool {
-        false
+        self.inner.supports_get_partial()
     }
 }
 
@@ -152,3 +195,31 @@ impl<S: AsyncListableStorageTraits> AsyncListableStorageTraits for ImplicitGroup
         self.inner.size().await
     }
 }
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl<S: AsyncWritableStorageTraits> AsyncWritableStorageTraits for ImplicitGroupStoreAdapter<S> {
+    async fn set(&self, key: &StoreKey, value: Bytes) -> Result<(), StorageError> {
+        self.inner.set(key, value).await
+    }
+
+    async fn set_partial_many<'a>(
+        &'a self,
+        key: &StoreKey,
+        offset_values: OffsetBytesIterator<'a>,
+    ) -> Result<(), StorageError> {
+        self.inner.set_partial_many(key, offset_values).await
+    }
+
+    async fn erase(&self, key: &StoreKey) -> Result<(), StorageError> {
+        self.inner.erase(key).await
+    }
+
+    async fn erase_prefix(&self, prefix: &StorePrefix) -> Result<(), StorageError> {
+        self.inner.erase_prefix(prefix).await
+    }
+
+    fn supports_set_partial(&self) -> bool {
+        self.inner.supports_set_partial()
+    }
+}
@@ -3,8 +3,9 @@ use zarrs::{
     group::GroupMetadataV3,
     metadata::v3::NodeMetadataV3,
     storage::{
-        ListableStorageTraits, MaybeBytes, MaybeBytesIterator, ReadableListableStorageTraits,
-        ReadableStorageTraits, StorageError, StoreKey, StoreKeys, StoreKeysPrefixes, StorePrefix,
+        ListableStorageTraits, MaybeBytes, MaybeBytesIterator, OffsetBytesIterator,
+        ReadableListableStorageTraits, ReadableStorageTraits, StorageError, StoreKey, StoreKeys,
+        StoreKeysPrefixes, StorePrefix, WritableStorageTraits,
         byte_range::{ByteRange, ByteRangeIterator},
     },
 };
@@ -12,16 +13,21 @@ use zarrs::{
 #[cfg(feature = "async")]
 mod asynch;
 
-use crate::{N5BlockHeader, N5BlockMode, metadata::N5Metadata};
+use crate::{N5BlockMode, N5EdgeBlockPolicy, metadata::N5Metadata};
 
 /// Which array type to assume when converting N5 array metadata to Zarr metadata.
-///
-/// VarLength and Object are not yet supported.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum N5ArrayMode {
+    /// Blocks hold an element for each point in their header shape; see [crate::N5DefaultCodec].
     #[default]
     Default,
+    /// Blocks hold any number of elements, and are read as a single Zarr `bytes` element each;
+    /// see [crate::N5VarLengthCodec].
+    ///
+    /// Default-mode blocks are also read this way.
     VarLength,
+    /// Blocks hold an opaque payload, and are read as a single Zarr `bytes` element each;
+    /// see [crate::N5ObjectCodec].
     Object,
 }
 
@@ -30,7 +36,7 @@ impl From<N5BlockMode> for N5ArrayMode {
         match value {
             N5BlockMode::Default => N5ArrayMode::Default,
             N5BlockMode::VarLength { .. } => N5ArrayMode::VarLength,
-            N5BlockMode::Object => N5ArrayMode::Object,
+            N5BlockMode::Object { .. } => N5ArrayMode::Object,
         }
     }
 }
@@ -38,22 +44,38 @@ impl From<N5BlockMode> for N5ArrayMode {
 /// An N5 store wrapping another Zarr store,
 /// which handles converting metadata.
 ///
+/// Reads of `zarr.json` are converted from the N5 `attributes.json`,
+/// and writes of `zarr.json` are converted back and merged into `attributes.json`
+/// (see [N5Metadata::merge_into]); all other keys (i.e. blocks) pass through unchanged.
+/// Merging is not atomic, so concurrent metadata writes to the same node may be lost.
+/// Erasing `zarr.json` erases `attributes.json` and, for arrays, the array's blocks;
+/// child nodes (and any blocks sharing a directory with them) are left in place.
+/// Erasing a prefix erases everything under it, including child nodes.
+/// Arrays created through this adapter should use the [crate::N5DefaultCodec],
+/// the `v2` chunk key encoding with a `/` separator,
+/// and a [zarrs::array::chunk_grid::RegularBoundedChunkGrid] so that edge blocks are truncated.
+///
 /// You may also want to wrap this in an [ImplicitGroupStoreAdapter] if you want to treat missing N5 metadata as empty groups,
 /// per the N5 spec.
 #[derive(Debug, Clone)]
 pub struct N5StoreAdapter<S> {
     inner: S,
     array_mode: N5ArrayMode,
+    edge_block_policy: N5EdgeBlockPolicy,
+    detect_compression: bool,
 }
 
 impl<S> N5StoreAdapter<S> {
     /// Create an N5 store wrapping some other store.
     /// The wrapper inherits the inner store's capabilities
-    /// (sync, async, readable, listable).
+    /// (sync, async, readable, writable, listable);
+    /// writing also requires the inner store to be readable and listable.
     pub fn new(inner: S) -> Self {
         Self {
             inner,
             array_mode: N5ArrayMode::Default,
+            edge_block_policy: N5EdgeBlockPolicy::default(),
+            detect_compression: false,
         }
     }
 
@@ -62,6 +84,19 @@ impl<S> N5StoreAdapter<S> {
         std::mem::replace(&mut self.array_mode, mode)
     }
 
+    /// Set a new policy for writing edge blocks of all arrays, returning the old policy.
+    pub fn set_edge_block_policy(&mut self, policy: N5EdgeBlockPolicy) -> N5EdgeBlockPolicy {
+        std::mem::replace(&mut self.edge_block_policy, policy)
+    }
+
+    /// Set whether to detect the compression of each block of all arrays when reading,
+    /// rather than trusting the metadata, returning the old setting.
+    ///
+    /// See [crate::N5DefaultCodec::with_compression_detection].
+    pub fn set_compression_detection(&mut self, detect_compression: bool) -> bool {
+        std::mem::replace(&mut self.detect_compression, detect_compression)
+    }
+
     /// Map requests for zarr.json to attributes.json.
     ///
     /// Returns None if the request was _not_ for a zarr.json object.
@@ -104,12 +139,18 @@ impl<S> N5StoreAdapter<S> {
         let node_meta = match n5meta {
             N5Metadata::Group(g) => NodeMetadataV3::Group(g.into()),
             N5Metadata::Array(a) => {
-                let ameta = a.try_into_zarr(self.array_mode).map_err(|e| {
-                    StorageError::InvalidMetadata(
-                        store_key.clone(),
-                        format!("could not convert N5 array metadata to Zarr metadata: {e}"),
+                let ameta = a
+                    .into_zarr(
+                        self.array_mode,
+                        self.edge_block_policy,
+                        self.detect_compression,
                     )
-                })?;
+                    .map_err(|e| {
+                        StorageError::InvalidMetadata(
+                            store_key.clone(),
+                            format!("could not convert N5 array metadata to Zarr metadata: {e}"),
+                        )
+                    })?;
                 NodeMetadataV3::Array(ameta)
             }
         };
@@ -122,30 +163,89 @@ impl<S> N5StoreAdapter<S> {
         }
     }
 
+    /// Convert Zarr metadata which is being written into N5 metadata,
+    /// merged into the existing N5 metadata document if there is one.
+    fn convert_zarr_metadata(
+        &self,
+        store_key: &StoreKey,
+        zarr_meta_bytes: &Bytes,
+        existing_n5_meta_bytes: Option<Bytes>,
+    ) -> Result<Bytes, StorageError> {
+        let zarr_meta: NodeMetadataV3 = serde_json::from_slice(zarr_meta_bytes).map_err(|e| {
+            StorageError::InvalidMetadata(
+                store_key.clone(),
+                format!("could not parse Zarr metadata: {e}"),
+            )
+        })?;
+        let n5_meta = N5Metadata::try_from(&zarr_meta).map_err(|e| {
+            StorageError::InvalidMetadata(
+                store_key.clone(),
+                format!("could not convert Zarr metadata to N5 metadata: {e}"),
+            )
+        })?;
+        let existing = match existing_n5_meta_bytes {
+            Some(b) => serde_json::from_slice(&b).map_err(|e| {
+                StorageError::InvalidMetadata(
+                    store_key.clone(),
+                    format!("could not parse existing N5 metadata: {e}"),
+                )
+            })?,
+            None => Default::default(),
+        };
+        let merged = n5_meta.merge_into(existing).map_err(|e| {
+            StorageError::InvalidMetadata(
+                store_key.clone(),
+                format!("could not merge N5 metadata: {e}"),
+            )
+        })?;
+        match serde_json::to_vec(&merged) {
+            Ok(v) => Ok(Bytes::from_owner(v)),
+            Err(e) => Err(StorageError::InvalidMetadata(
+                store_key.clone(),
+                format!("could not serialize N5 metadata: {e}"),
+            )),
+        }
+    }
+
     /// Retrieve the inner store.
     pub fn into_inner(self) -> S {
         self.inner
     }
 
-    /// Iterate through the keys which look like N5 blocks in the given prefix.
-    fn filter_chunk_keys(
-        &self,
-        prefix: &StorePrefix,
-        keys: Vec<StoreKey>,
-    ) -> impl Iterator<Item = StoreKey> {
-        let prefix_len = prefix.as_str().len();
+    /// Get a reference to the inner store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
 
-        keys.into_iter().filter(move |k| {
-            let s = &k.as_str()[prefix_len..];
-            s.chars().all(|c| c.is_ascii_digit() || c == '/')
-        })
+    /// Get the policy for writing edge blocks.
+    pub fn edge_block_policy(&self) -> N5EdgeBlockPolicy {
+        self.edge_block_policy
     }
 
-    /// Read the block header and, if possible, return the block mode.
-    fn block_mode(&self, value: Option<Bytes>) -> Option<N5BlockMode> {
-        let v = value?;
-        let header = N5BlockHeader::from_bytes(&v).ok()?;
-        Some(header.mode)
+    /// If the N5 metadata being erased describes an array, return its prefix and dimensionality.
+    fn erased_array(
+        &self,
+        meta_key: &StoreKey,
+        n5_meta_bytes: Option<Bytes>,
+    ) -> Result<Option<(StorePrefix, usize)>, StorageError> {
+        let Some(b) = n5_meta_bytes else {
+            return Ok(None);
+        };
+        let n5_meta = serde_json::from_slice(&b).map_err(|e| {
+            StorageError::InvalidMetadata(
+                meta_key.clone(),
+                format!("could not parse N5 metadata: {e}"),
+            )
+        })?;
+        let N5Metadata::Array(a) = n5_meta else {
+            return Ok(None);
+        };
+        let prefix = meta_key
+            .as_str()
+            .strip_suffix(crate::N5_METADATA_KEY)
+            .and_then(|p| StorePrefix::new(p).ok())
+            .expect("N5 metadata key should be in a valid prefix");
+        Ok(Some((prefix, a.dimensions.len())))
     }
 }
 
@@ -158,8 +258,10 @@ impl<S: ReadableStorageTraits> ReadableStorageTraits for N5StoreAdapter<S> {
         }
     }
 
+    /// Partial reads of blocks are passed through to the inner store;
+    /// partial reads of Zarr metadata are not supported.
     fn supports_get_partial(&self) -> bool {
-        false
+        self.inner.supports_get_partial()
     }
 
     fn get(&self, key: &StoreKey) -> Result<MaybeBytes, StorageError> {
@@ -172,43 +274,91 @@ impl<S: ReadableStorageTraits> ReadableStorageTraits for N5StoreAdapter<S> {
 
     fn get_partial_many<'a>(
         &'a self,
-        _key: &StoreKey,
-        _byte_ranges: ByteRangeIterator<'a>,
+        key: &StoreKey,
+        byte_ranges: ByteRangeIterator<'a>,
     ) -> Result<MaybeBytesIterator<'a>, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial_many not supported".into(),
-        ))
+        if self.intercept_zarr_json(key).is_some() {
+            return Err(StorageError::Unsupported(
+                "partial reads of Zarr metadata not supported".into(),
+            ));
+        }
+        self.inner.get_partial_many(key, byte_ranges)
+    }
+}
+
+/// Writing requires the inner store to be readable,
+/// as N5 metadata is merged into the existing document,
+/// and listable, as erasing an array's metadata also erases its blocks.
+impl<S: ReadableStorageTraits + WritableStorageTraits + ListableStorageTraits> WritableStorageTraits
+    for N5StoreAdapter<S>
+{
+    fn set(&self, key: &StoreKey, value: Bytes) -> Result<(), StorageError> {
+        if let Some(meta_key) = self.intercept_zarr_json(key) {
+            let existing = self.inner.get(&meta_key)?;
+            let n5_value = self.convert_zarr_metadata(&meta_key, &value, existing)?;
+            self.inner.set(&meta_key, n5_value)
+        } else {
+            self.inner.set(key, value)
+        }
     }
 
-    fn get_partial(
+    fn set_partial_many(
         &self,
-        _key: &StoreKey,
-        _byte_range: ByteRange,
-    ) -> Result<MaybeBytes, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial not supported".into(),
-        ))
+        key: &StoreKey,
+        offset_values: OffsetBytesIterator,
+    ) -> Result<(), StorageError> {
+        if self.intercept_zarr_json(key).is_some() {
+            return Err(StorageError::Unsupported(
+                "partial writes of Zarr metadata not supported".into(),
+            ));
+        }
+        self.inner.set_partial_many(key, offset_values)
+    }
+
+    fn erase(&self, key: &StoreKey) -> Result<(), StorageError> {
+        if let Some(meta_key) = self.intercept_zarr_json(key) {
+            if let Some((prefix, ndim)) =
+                self.erased_array(&meta_key, self.inner.get(&meta_key)?)?
+            {
+                let keys = array_block_keys(&prefix, self.inner.list_prefix(&prefix)?, ndim);
+                self.inner.erase_many(&keys)?;
+            }
+            self.inner.erase(&meta_key)
+        } else {
+            self.inner.erase(key)
+        }
+    }
+
+    fn erase_prefix(&self, prefix: &StorePrefix) -> Result<(), StorageError> {
+        self.inner.erase_prefix(prefix)
+    }
+
+    fn supports_set_partial(&self) -> bool {
+        self.inner.supports_set_partial()
     }
 }
 
 impl<S: ReadableListableStorageTraits> N5StoreAdapter<S> {
+    /// Infer the array mode from the header of the array's first block,
+    /// or [None] if there are no blocks.
+    ///
+    /// N5 records the mode in each block, and n5-java writes a varlength block whenever
+    /// a block's element count differs from its shape, so a default-mode array may have some varlength blocks.
+    /// These are decoded according to their own header by [crate::N5DefaultCodec],
+    /// so only the first bytes of the first block are read.
     pub fn infer_array_mode(
         &self,
         prefix: &StorePrefix,
     ) -> Result<Option<N5ArrayMode>, StorageError> {
-        // TODO: could parallelise
-        for key in self.filter_chunk_keys(prefix, self.list_prefix(prefix)?) {
-            let Some(mode) = self.block_mode(self.get(&key)?) else {
-                continue;
-            };
-            return Ok(Some(mode.into()));
-        }
-        Ok(None)
+        infer_array_mode(&self.inner, prefix)
     }
 }
 
 /// Iterate through the keys which look like N5 blocks in the given prefix.
-fn filter_chunk_keys(prefix: &StorePrefix, keys: Vec<StoreKey>) -> impl Iterator<Item = StoreKey> {
+pub(crate) fn filter_chunk_keys(
+    prefix: &StorePrefix,
+    keys: Vec<StoreKey>,
+) -> impl Iterator<Item = StoreKey> {
     let prefix_len = prefix.as_str().len();
 
     keys.into_iter().filter(move |k| {
@@ -217,22 +367,59 @@ fn filter_chunk_keys(prefix: &StorePrefix, keys: Vec<StoreKey>) -> impl Iterator
     })
 }
 
-/// Read the block header and, if possible, return the block mode.
-fn block_mode(value: Option<Bytes>) -> Option<N5BlockMode> {
+/// Keys of the blocks of an `ndim`-dimensional array in the given prefix.
+///
+/// Keys belonging to child nodes (i.e. under a directory with its own `attributes.json`)
+/// are excluded, even if they look like blocks of this array.
+pub(crate) fn array_block_keys(
+    prefix: &StorePrefix,
+    keys: Vec<StoreKey>,
+    ndim: usize,
+) -> Vec<StoreKey> {
+    let prefix_len = prefix.as_str().len();
+    let child_prefixes: Vec<String> = keys
+        .iter()
+        .filter_map(|k| {
+            k.as_str()[prefix_len..]
+                .strip_suffix(crate::N5_METADATA_KEY)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+        })
+        .collect();
+    filter_chunk_keys(prefix, keys)
+        .filter(|k| {
+            let s = &k.as_str()[prefix_len..];
+            s.split('/').count() == ndim && !child_prefixes.iter().any(|c| s.starts_with(c))
+        })
+        .collect()
+}
+
+/// The start of a block header, which holds the block mode.
+pub(crate) const BLOCK_MODE_RANGE: ByteRange =
+    ByteRange::FromStart(0, Some(size_of::<u16>() as u64));
+
+/// Read the start of the block header and, if possible, return the array mode of the block.
+pub(crate) fn block_mode(value: Option<Bytes>) -> Option<N5ArrayMode> {
     let v = value?;
-    let header = N5BlockHeader::from_bytes(&v).ok()?;
-    Some(header.mode)
+    let mode = u16::from_be_bytes(v.get(..size_of::<u16>())?.try_into().ok()?);
+    match mode {
+        0 => Some(N5ArrayMode::Default),
+        1 => Some(N5ArrayMode::VarLength),
+        2 => Some(N5ArrayMode::Object),
+        _ => None,
+    }
 }
 
+/// Infer the array mode from the header of the array's first block; see [N5StoreAdapter::infer_array_mode].
 pub fn infer_array_mode<S: ReadableListableStorageTraits + ?Sized>(
     store: &S,
     prefix: &StorePrefix,
 ) -> Result<Option<N5ArrayMode>, StorageError> {
     for key in filter_chunk_keys(prefix, store.list_prefix(prefix)?) {
-        let Some(mode) = block_mode(store.get(&key)?) else {
+        let Some(mode) = block_mode(store.get_partial(&key, BLOCK_MODE_RANGE)?) else {
             continue;
         };
-        return Ok(Some(mode.into()));
+        return Ok(Some(mode));
     }
     Ok(None)
 }
@@ -271,7 +458,7 @@ pub struct ImplicitGroupStoreAdapter<S> {
 impl<S> ImplicitGroupStoreAdapter<S> {
     /// Create an implicit group adapter wrapping some other store.
     /// The wrapper inherits the inner store's capabilities
-    /// (sync, async, readable, listable).
+    /// (sync, async, readable, writable, listable).
     ///
     /// All implicit groups will have the given attributes.
     pub fn new_with_attributes(
@@ -284,7 +471,7 @@ impl<S> ImplicitGroupStoreAdapter<S> {
 
     /// Create an implicit group adapter wrapping some other store.
     /// The wrapper inherits the inner store's capabilities
-    /// (sync, async, readable, listable).
+    /// (sync, async, readable, writable, listable).
     ///
     /// See [Self::new_with_attributes] to apply some attributes to all implicit groups.
     pub fn new(inner_store: S) -> Self {
@@ -323,25 +510,30 @@ impl<S> ImplicitGroupStoreAdapter<S> {
             return Some(v);
         }
 
-        let s = key.as_str();
-        let suffix = match s.rsplit_once('/') {
-            Some((_, suf)) => suf,
-            None => s,
-        };
-        if suffix == "zarr.json" {
+        if is_zarr_json(key) {
             return Some(self.implicit_metadata.clone());
         }
         None
     }
 }
 
+fn is_zarr_json(key: &StoreKey) -> bool {
+    let s = key.as_str();
+    let suffix = match s.rsplit_once('/') {
+        Some((_, suf)) => suf,
+        None => s,
+    };
+    suffix == "zarr.json"
+}
+
 impl<S: ReadableStorageTraits> ReadableStorageTraits for ImplicitGroupStoreAdapter<S> {
     fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
         self.inner.size_key(key)
     }
 
+    /// Partial reads are passed through to the inner store, except for Zarr metadata.
     fn supports_get_partial(&self) -> bool {
-        false
+        self.inner.supports_get_partial()
     }
 
     fn get(&self, key: &StoreKey) -> Result<MaybeBytes, StorageError> {
@@ -351,22 +543,43 @@ impl<S: ReadableStorageTraits> ReadableStorageTraits for ImplicitGroupStoreAdapt
 
     fn get_partial_many<'a>(
         &'a self,
-        _key: &StoreKey,
-        _byte_ranges: ByteRangeIterator<'a>,
+        key: &StoreKey,
+        byte_ranges: ByteRangeIterator<'a>,
     ) -> Result<MaybeBytesIterator<'a>, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial_many not supported".into(),
-        ))
+        if is_zarr_json(key) {
+            return Err(StorageError::Unsupported(
+                "partial reads of Zarr metadata not supported".into(),
+            ));
+        }
+        self.inner.get_partial_many(key, byte_ranges)
+    }
+}
+
+/// Writes pass through unchanged;
+/// an implicit group becomes explicit when its metadata is written.
+impl<S: WritableStorageTraits> WritableStorageTraits for ImplicitGroupStoreAdapter<S> {
+    fn set(&self, key: &StoreKey, value: Bytes) -> Result<(), StorageError> {
+        self.inner.set(key, value)
     }
 
-    fn get_partial(
+    fn set_partial_many(
         &self,
-        _key: &StoreKey,
-        _byte_range: ByteRange,
-    ) -> Result<MaybeBytes, StorageError> {
-        Err(StorageError::Unsupported(
-            "get_partial not supported".into(),
-        ))
+        key: &StoreKey,
+        offset_values: OffsetBytesIterator,
+    ) -> Result<(), StorageError> {
+        self.inner.set_partial_many(key, offset_values)
+    }
+
+    fn erase(&self, key: &StoreKey) -> Result<(), StorageError> {
+        self.inner.erase(key)
+    }
+
+    fn erase_prefix(&self, prefix: &StorePrefix) -> Result<(), StorageError> {
+        self.inner.erase_prefix(prefix)
+    }
+
+    fn supports_set_partial(&self) -> bool {
+        self.inner.supports_set_partial()
     }
 }
 
@@ -0,0 +1,101 @@
+#![cfg(feature = "async")]
+mod common;
+
+use common::{block_keys, inner_memory_store, read_raw};
+use std::sync::Arc;
+use zarrs::array::{Array, ArraySubset};
+use zarrs::group::Group;
+use zarrs::storage::storage_adapter::sync_to_async::{
+    SyncToAsyncSpawnBlocking, SyncToAsyncStorageAdapter,
+};
+use zarrs::storage::{ReadableStorageTraits, WritableStorageTraits};
+use zarrs_n5::{ImplicitGroupStoreAdapter, N5StoreAdapter};
+
+/// Runs "blocking" work inline, which is fine for an in-memory store.
+struct Inline;
+
+impl SyncToAsyncSpawnBlocking for Inline {
+    async fn spawn_blocking<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        f()
+    }
+}
+
+#[test]
+fn test_async_write() {
+    let inner = Arc::new(inner_memory_store("gzip"));
+    let (_, raw_data) = read_raw();
+    let blocks = block_keys(&inner);
+    inner.erase_many(&blocks).unwrap();
+    assert!(!blocks.is_empty());
+
+    let store = Arc::new(ImplicitGroupStoreAdapter::new(N5StoreAdapter::new(
+        SyncToAsyncStorageAdapter::new(inner.clone(), Inline),
+    )));
+    futures::executor::block_on(async {
+        let mut array = Array::async_open(store.clone(), "/").await.unwrap();
+        array
+            .async_store_array_subset(&array.subset_all(), raw_data.as_slice())
+            .await
+            .unwrap();
+        array.attributes_mut().insert("foo".into(), "bar".into());
+        array.async_store_metadata().await.unwrap();
+
+        let data: Vec<f32> = array
+            .async_retrieve_array_subset(&array.subset_all())
+            .await
+            .unwrap();
+        assert_eq!(data, raw_data);
+
+        // implicit groups can be made explicit
+        let group = Group::async_open(store.clone(), "/group").await.unwrap();
+        group.async_store_metadata().await.unwrap();
+    });
+
+    for key in blocks {
+        assert!(inner.get(&key).unwrap().is_some(), "{key} not written");
+    }
+    let attrs: serde_json::Value = serde_json::from_slice(
+        &inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(attrs["foo"], "bar");
+    assert_eq!(attrs["compression"]["type"], "gzip");
+    assert!(
+        inner
+            .get(&"group/attributes.json".try_into().unwrap())
+            .unwrap()
+            .is_some()
+    );
+}
+
+#[test]
+fn test_async_partial_encode() {
+    let inner = Arc::new(inner_memory_store("even_chunk"));
+    let store = Arc::new(N5StoreAdapter::new(SyncToAsyncStorageAdapter::new(
+        inner.clone(),
+        Inline,
+    )));
+    let options =
+        zarrs::array::codec::api::CodecOptions::default().with_experimental_partial_encoding(true);
+    futures::executor::block_on(async {
+        let array = Array::async_open(store, "/").await.unwrap();
+        let subset = ArraySubset::new_with_ranges(&[0..1, 0..2]);
+        array
+            .async_store_chunk_subset_opt(&[0, 0], &subset, vec![-1f32; 2], &options)
+            .await
+            .unwrap();
+        let data: Vec<f32> = array
+            .async_retrieve_array_subset(&ArraySubset::new_with_ranges(&[0..1, 0..3]))
+            .await
+            .unwrap();
+        let (_, raw_data) = read_raw();
+        assert_eq!(data, [-1.0, -1.0, raw_data[2]]);
+    });
+}
@@ -0,0 +1,689 @@
+mod common;
+
+use common::read_raw;
+use std::borrow::Cow;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+use zarrs::array::codec::Crc32cCodec;
+use zarrs::array::codec::api::{
+    ArrayCodecTraits, BytesRepresentation, BytesToBytesCodecTraits, CodecOptions,
+};
+use zarrs::array::{Array, data_type};
+use zarrs::metadata::v3::MetadataV3;
+use zarrs::plugin::ExtensionAliasesV3;
+use zarrs::storage::store::MemoryStore;
+use zarrs::storage::{ListableStorageTraits, ReadableStorageTraits, WritableStorageTraits};
+use zarrs_n5::{
+    N5ArrayBuilder, N5ArrayMetadata, N5ArrayMode, N5Compression, N5CompressionPlugin,
+    N5CompressionTraits, N5DefaultCodec, N5Lz4Codec, N5StoreAdapter, N5XzCodec,
+};
+
+fn decode(codec: &dyn BytesToBytesCodecTraits, encoded: &[u8]) -> Vec<u8> {
+    codec
+        .decode(
+            Cow::Borrowed(encoded),
+            &BytesRepresentation::UnboundedSize,
+            &CodecOptions::default(),
+        )
+        .unwrap()
+        .into_owned()
+}
+
+/// A block in the framing of lz4-java's `LZ4BlockOutputStream`.
+fn lz4_block(token: u8, compressed: &[u8], original_len: u32, checksum: u32) -> Vec<u8> {
+    let mut out = b"LZ4Block".to_vec();
+    out.push(token);
+    out.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    out.extend_from_slice(&original_len.to_le_bytes());
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out.extend_from_slice(compressed);
+    out
+}
+
+#[test]
+fn test_lz4_decode_java_framing() {
+    // checksums are xxhash32 with lz4-java's seed, masked to 28 bits
+    let mut encoded = lz4_block(0x16, b"0123456789abcdef0123", 20, 0x0c45d4fa);
+    encoded.extend(lz4_block(0x26, b"\x50hello", 5, 0x0a41bfe3));
+    encoded.extend(lz4_block(0x16, b"", 0, 0));
+
+    let codec = N5Lz4Codec::new(65536).unwrap();
+    assert_eq!(decode(&codec, &encoded), b"0123456789abcdef0123hello");
+
+    // the final empty block is optional
+    let truncated = &encoded[..encoded.len() - 21];
+    assert_eq!(decode(&codec, truncated), b"0123456789abcdef0123hello");
+
+    let mut corrupted = encoded.clone();
+    corrupted[21] = b'X';
+    assert!(
+        codec
+            .decode(
+                Cow::Owned(corrupted),
+                &BytesRepresentation::UnboundedSize,
+                &CodecOptions::default(),
+            )
+            .is_err()
+    );
+}
+
+#[test]
+fn test_lz4_encode_blocks() {
+    let codec = N5Lz4Codec::new(100).unwrap();
+    let data: Vec<u8> = (0..250u32).map(|i| (i % 7) as u8).collect();
+    let encoded = codec
+        .encode(Cow::Borrowed(&data), &CodecOptions::default())
+        .unwrap();
+
+    // 3 blocks of at most 100 bytes, then an empty block
+    let mut offset = 0;
+    let mut lengths = Vec::default();
+    loop {
+        let header = &encoded[offset..offset + 21];
+        assert_eq!(&header[..8], b"LZ4Block");
+        // block sizes up to 2^10 share the lowest level
+        assert_eq!(header[8] & 0x0F, 0);
+        let compressed_len = u32::from_le_bytes(header[9..13].try_into().unwrap()) as usize;
+        let original_len = u32::from_le_bytes(header[13..17].try_into().unwrap());
+        lengths.push(original_len);
+        offset += 21 + compressed_len;
+        if original_len == 0 {
+            break;
+        }
+        assert_eq!(header[8] & 0xF0, 0x20, "compressible block should be LZ4");
+    }
+    assert_eq!(offset, encoded.len());
+    assert_eq!(lengths, [100, 100, 50, 0]);
+    assert_eq!(decode(&codec, &encoded), data);
+
+    // incompressible blocks are stored raw
+    let codec = N5Lz4Codec::new(1 << 20).unwrap();
+    let encoded = codec
+        .encode(Cow::Borrowed(b"hello"), &CodecOptions::default())
+        .unwrap();
+    assert_eq!(encoded[8], 0x10 | 10);
+    assert_eq!(&encoded[21..26], b"hello");
+    assert_eq!(decode(&codec, &encoded), b"hello");
+
+    assert!(N5Lz4Codec::new(63).is_err());
+    assert!(N5Lz4Codec::new((1 << 25) + 1).is_err());
+}
+
+#[test]
+fn test_lz4_array() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let (shape, raw_data) = read_raw();
+    let array = N5ArrayBuilder::new(shape, vec![NonZeroU64::new(64).unwrap(); 2], "float32")
+        .compression(N5Compression::Lz4 { level: 4096 })
+        .build(store.clone(), "/")
+        .unwrap();
+    array
+        .store_array_subset(&array.subset_all(), raw_data.as_slice())
+        .unwrap();
+
+    let attrs: serde_json::Value = serde_json::from_slice(
+        &inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        attrs["compression"],
+        serde_json::json!({"type": "lz4", "level": 4096})
+    );
+    let block = inner.get(&"0/0".try_into().unwrap()).unwrap().unwrap();
+    assert_eq!(&block[12..20], b"LZ4Block");
+
+    let array = Array::open(store, "/").unwrap();
+    let data: Vec<f32> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    assert_eq!(data, raw_data);
+}
+
+#[test]
+fn test_xz_decode() {
+    // python: lzma.compress(b"hello xz", format=lzma.FORMAT_XZ, check=lzma.CHECK_CRC64, preset=6)
+    let encoded = [
+        0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x04, 0xe6, 0xd6, 0xb4, 0x46, 0x02, 0x00, 0x21,
+        0x01, 0x16, 0x00, 0x00, 0x00, 0x74, 0x2f, 0xe5, 0xa3, 0x01, 0x00, 0x07, 0x68, 0x65, 0x6c,
+        0x6c, 0x6f, 0x20, 0x78, 0x7a, 0x00, 0x86, 0x8f, 0x9b, 0x4b, 0x49, 0x08, 0xe0, 0xa4, 0x00,
+        0x01, 0x20, 0x08, 0xbb, 0x19, 0xd9, 0xbb, 0x1f, 0xb6, 0xf3, 0x7d, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x04, 0x59, 0x5a,
+    ];
+    let codec = N5XzCodec::new(6).unwrap();
+    assert_eq!(decode(&codec, &encoded), b"hello xz");
+
+    let encoded = codec
+        .encode(Cow::Borrowed(b"hello xz"), &CodecOptions::default())
+        .unwrap();
+    assert_eq!(&encoded[..6], b"\xfd7zXZ\x00");
+    assert_eq!(decode(&codec, &encoded), b"hello xz");
+
+    assert!(N5XzCodec::new(0).is_ok());
+    assert!(N5XzCodec::new(10).is_err());
+}
+
+#[test]
+fn test_xz_array() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let (shape, raw_data) = read_raw();
+    let array = N5ArrayBuilder::new(shape, vec![NonZeroU64::new(64).unwrap(); 2], "float32")
+        .compression(N5Compression::Xz { preset: 1 })
+        .build(store.clone(), "/")
+        .unwrap();
+    array
+        .store_array_subset(&array.subset_all(), raw_data.as_slice())
+        .unwrap();
+
+    // the Zarr metadata names the XZ codec inside the N5 codec's configuration
+    let zarr_json: serde_json::Value = serde_json::from_slice(
+        &store
+            .get(&"zarr.json".try_into().unwrap())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    let n5_codec = &zarr_json["codecs"][0];
+    assert_eq!(n5_codec["name"], "n5_default");
+    let inner_codecs = n5_codec["configuration"]["codecs"].as_array().unwrap();
+    assert_eq!(
+        inner_codecs.last().unwrap(),
+        &serde_json::json!({"name": "n5_xz", "configuration": {"preset": 1}})
+    );
+
+    let attrs: serde_json::Value = serde_json::from_slice(
+        &inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        attrs["compression"],
+        serde_json::json!({"type": "xz", "preset": 1})
+    );
+
+    let array = Array::open(store, "/").unwrap();
+    let data: Vec<f32> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    assert_eq!(data, raw_data);
+}
+
+#[test]
+fn test_zlib_array() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let (shape, raw_data) = read_raw();
+    let array = N5ArrayBuilder::new(shape, vec![NonZeroU64::new(64).unwrap(); 2], "float32")
+        .compression(N5Compression::Gzip {
+            level: -1,
+            use_zlib: true,
+        })
+        .build(store.clone(), "/")
+        .unwrap();
+    array
+        .store_array_subset(&array.subset_all(), raw_data.as_slice())
+        .unwrap();
+
+    // a zlib header: deflate with a 32KiB window, default compression
+    let block = inner.get(&"0/0".try_into().unwrap()).unwrap().unwrap();
+    assert_eq!(&block[12..14], b"\x78\x9c");
+
+    let array = Array::open(store, "/").unwrap();
+    let data: Vec<f32> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    assert_eq!(data, raw_data);
+}
+
+#[test]
+fn test_zstd_checksums() {
+    for use_checksums in [false, true] {
+        let inner = Arc::new(MemoryStore::default());
+        let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+        let array = N5ArrayBuilder::new(vec![8, 8], vec![NonZeroU64::new(8).unwrap(); 2], "uint8")
+            .compression(N5Compression::Zstd {
+                level: 3,
+                use_checksums,
+                nb_workers: 0,
+            })
+            .build(store.clone(), "/")
+            .unwrap();
+        let data: Vec<u8> = (0..64).collect();
+        array.store_chunk(&[0, 0], data.as_slice()).unwrap();
+
+        let key = "0/0".try_into().unwrap();
+        let mut block = inner.get(&key).unwrap().unwrap().to_vec();
+        // the frame header descriptor follows the 12-byte N5 header and 4-byte zstd magic
+        assert_eq!(block[16] & 0b100 != 0, use_checksums);
+        let decoded: Vec<u8> = array.retrieve_chunk(&[0, 0]).unwrap();
+        assert_eq!(decoded, data);
+
+        // corrupting the checksum is detected
+        if use_checksums {
+            *block.last_mut().unwrap() ^= 0xff;
+            inner.set(&key, block.into()).unwrap();
+            assert!(array.retrieve_chunk::<Vec<u8>>(&[0, 0]).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_blosc_typesize_from_data_type() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let compression: N5Compression = serde_json::from_value(
+        serde_json::json!({"type": "blosc", "cname": "lz4", "clevel": 5, "shuffle": -1, "nthreads": 16}),
+    )
+    .unwrap();
+    let array = N5ArrayBuilder::new(vec![8, 8], vec![NonZeroU64::new(8).unwrap(); 2], "uint16")
+        .compression(compression.clone())
+        .build(store.clone(), "/")
+        .unwrap();
+    let data: Vec<u16> = (0..64).collect();
+    array.store_chunk(&[0, 0], data.as_slice()).unwrap();
+
+    // the blosc header follows the 12-byte N5 header
+    let block = inner.get(&"0/0".try_into().unwrap()).unwrap().unwrap();
+    let (flags, typesize) = (block[14], block[15]);
+    assert_eq!(typesize, 2);
+    assert_eq!(flags & 0b1, 1, "byte shuffle should be enabled");
+    let decoded: Vec<u16> = array.retrieve_chunk(&[0, 0]).unwrap();
+    assert_eq!(decoded, data);
+
+    let codec = N5DefaultCodec::new(
+        compression
+            .to_bytes_to_bytes_codec_for_data_type("uint16")
+            .unwrap(),
+        2,
+    )
+    .with_concurrency(compression.concurrency().unwrap());
+    let recommended = codec
+        .recommended_concurrency(&[NonZeroU64::new(8).unwrap(); 2], &data_type::uint16())
+        .unwrap();
+    assert_eq!(recommended.max(), 16);
+}
+
+/// An 8-bit greyscale JPEG, as n5-jpeg writes for each slice of a block.
+#[cfg(feature = "jpeg")]
+fn jpeg_slice(pixels: &[u8], width: u16, height: u16) -> Vec<u8> {
+    let mut out = Vec::default();
+    jpeg_encoder::Encoder::new(&mut out, 100)
+        .encode(pixels, width, height, jpeg_encoder::ColorType::Luma)
+        .unwrap();
+    out
+}
+
+#[cfg(feature = "jpeg")]
+#[test]
+fn test_jpeg_array() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    // JPEG is decode-only, so the array cannot be built
+    let attributes = serde_json::json!({
+        "dimensions": [8, 4, 2],
+        "blockSize": [8, 4, 2],
+        "dataType": "uint8",
+        "compression": {"type": "jpeg", "quality": 100},
+    });
+    inner
+        .set(
+            &"attributes.json".try_into().unwrap(),
+            serde_json::to_vec(&attributes).unwrap().into(),
+        )
+        .unwrap();
+    let array = Array::open(store.clone(), "/").unwrap();
+
+    let value = |x: u8, y: u8, z: u8| 100 * z + 16 * y + x;
+    // each slice is an 8x4 image, in N5's x-fastest order
+    let mut block = vec![0, 0, 0, 3, 0, 0, 0, 8, 0, 0, 0, 4, 0, 0, 0, 2];
+    for z in 0..2 {
+        let slice: Vec<u8> = (0..4)
+            .flat_map(|y| (0..8).map(move |x| value(x, y, z)))
+            .collect();
+        block.extend(jpeg_slice(&slice, 8, 4));
+    }
+    inner
+        .set(&"0/0/0".try_into().unwrap(), block.into())
+        .unwrap();
+
+    let expected: Vec<u8> = (0..8)
+        .flat_map(|x| (0..4).flat_map(move |y| (0..2).map(move |z| value(x, y, z))))
+        .collect();
+    let data: Vec<u8> = array.retrieve_chunk(&[0, 0, 0]).unwrap();
+    assert_eq!(data.len(), expected.len());
+    // JPEG is lossy even at full quality
+    for (actual, expected) in data.iter().zip(&expected) {
+        assert!(actual.abs_diff(*expected) <= 2, "{actual} != {expected}");
+    }
+
+    // slices must match the block's x and y extent, even if they have as many pixels
+    let mut block = vec![0, 0, 0, 3, 0, 0, 0, 8, 0, 0, 0, 4, 0, 0, 0, 2];
+    for z in 0..2 {
+        let slice: Vec<u8> = (0..32u8).map(|i| value(i % 4, i / 4, z)).collect();
+        block.extend(jpeg_slice(&slice, 4, 8));
+    }
+    inner
+        .set(&"0/0/0".try_into().unwrap(), block.into())
+        .unwrap();
+    assert!(array.retrieve_chunk::<Vec<u8>>(&[0, 0, 0]).is_err());
+
+    // encoding is not supported
+    assert!(array.store_chunk(&[0, 0, 0], expected.as_slice()).is_err());
+
+    // existing arrays can have their metadata updated
+    array.store_metadata().unwrap();
+    // but new arrays cannot be created
+    let mut zarr_meta = array.metadata().clone();
+    let zarrs::array::ArrayMetadata::V3(zarr_meta) = &mut zarr_meta else {
+        panic!("should be Zarr v3 metadata");
+    };
+    zarr_meta.attributes.remove("_n5");
+    assert!(N5ArrayMetadata::try_from(&*zarr_meta).is_err());
+    let new_array = Array::new_with_metadata(
+        store.clone(),
+        "/new",
+        zarrs::array::ArrayMetadata::V3(zarr_meta.clone()),
+    )
+    .unwrap();
+    assert!(new_array.store_metadata().is_err());
+    assert!(
+        inner
+            .get(&"new/attributes.json".try_into().unwrap())
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[cfg(feature = "jpeg")]
+#[test]
+fn test_jpeg_requires_uint8() {
+    let compression = N5Compression::Jpeg { quality: 90 };
+    assert!(
+        compression
+            .to_bytes_to_bytes_codec_for_data_type("uint8")
+            .is_ok()
+    );
+    assert!(
+        compression
+            .to_bytes_to_bytes_codec_for_data_type("uint16")
+            .is_err()
+    );
+}
+
+/// A third-party compression which appends a CRC32C checksum to each block.
+struct Crc32cCompression;
+
+impl N5CompressionTraits for Crc32cCompression {
+    const TYPE: &'static str = "crc32c";
+
+    fn create(
+        _compression: &serde_json::Map<String, serde_json::Value>,
+    ) -> zarrs_n5::Result<Arc<dyn BytesToBytesCodecTraits>> {
+        Ok(Arc::new(Crc32cCodec::new()))
+    }
+
+    fn try_from_codec_metadata(
+        metadata: &MetadataV3,
+    ) -> Option<serde_json::Map<String, serde_json::Value>> {
+        Crc32cCodec::matches_name_v3(metadata.name()).then(Default::default)
+    }
+}
+
+inventory::submit! {
+    N5CompressionPlugin::new::<Crc32cCompression>()
+}
+
+/// A third-party compression which can only be read.
+struct ReadOnlyCompression;
+
+impl N5CompressionTraits for ReadOnlyCompression {
+    const TYPE: &'static str = "read-only";
+
+    fn create(
+        _compression: &serde_json::Map<String, serde_json::Value>,
+    ) -> zarrs_n5::Result<Arc<dyn BytesToBytesCodecTraits>> {
+        Ok(Arc::new(Crc32cCodec::new()))
+    }
+
+    fn can_encode() -> bool {
+        false
+    }
+}
+
+inventory::submit! {
+    N5CompressionPlugin::new::<ReadOnlyCompression>()
+}
+
+/// A third-party compression which claims a built-in `type`, and so is ignored.
+struct ShadowGzipCompression;
+
+impl N5CompressionTraits for ShadowGzipCompression {
+    const TYPE: &'static str = "gzip";
+
+    fn create(
+        _compression: &serde_json::Map<String, serde_json::Value>,
+    ) -> zarrs_n5::Result<Arc<dyn BytesToBytesCodecTraits>> {
+        Ok(Arc::new(Crc32cCodec::new()))
+    }
+
+    fn try_from_codec_metadata(
+        _metadata: &MetadataV3,
+    ) -> Option<serde_json::Map<String, serde_json::Value>> {
+        Some(Default::default())
+    }
+}
+
+inventory::submit! {
+    N5CompressionPlugin::new::<ShadowGzipCompression>()
+}
+
+#[test]
+fn test_compression_plugin() {
+    let compression: N5Compression =
+        serde_json::from_value(serde_json::json!({"type": "crc32c"})).unwrap();
+    assert!(matches!(compression, N5Compression::Plugin(_)));
+
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let data: Vec<u16> = (0..64).collect();
+    let array = N5ArrayBuilder::new(vec![8, 8], vec![NonZeroU64::new(8).unwrap(); 2], "uint16")
+        .compression(compression.clone())
+        .build(store.clone(), "/")
+        .unwrap();
+    array.store_chunk(&[0, 0], data.as_slice()).unwrap();
+
+    let attrs: serde_json::Value = serde_json::from_slice(
+        &inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(attrs["compression"], serde_json::json!({"type": "crc32c"}));
+    let block = inner.get(&"0/0".try_into().unwrap()).unwrap().unwrap();
+    assert_eq!(block.len(), 12 + 64 * 2 + 4);
+
+    let array = Array::open(store, "/").unwrap();
+    let decoded: Vec<u16> = array.retrieve_chunk(&[0, 0]).unwrap();
+    assert_eq!(decoded, data);
+
+    // without the stashed N5 metadata, the plugin recovers the compression from the codec
+    let n5_meta: N5ArrayMetadata = serde_json::from_value(attrs).unwrap();
+    let mut zarr_meta = n5_meta.try_into_zarr(N5ArrayMode::Default).unwrap();
+    zarr_meta.attributes.remove("_n5");
+    assert_eq!(
+        N5ArrayMetadata::try_from(&zarr_meta).unwrap().compression,
+        compression
+    );
+
+    let unknown: N5Compression =
+        serde_json::from_value(serde_json::json!({"type": "unknown", "level": 1})).unwrap();
+    assert!(unknown.to_bytes_to_bytes_codec().is_err());
+    assert!(!unknown.can_encode());
+}
+
+#[test]
+fn test_built_in_compression_is_not_plugin() {
+    // invalid built-in compression objects are errors, rather than plugin compressions
+    for compression in [
+        serde_json::json!({"type": "gzip", "level": "high"}),
+        serde_json::json!({"type": "blosc", "cname": "unknown"}),
+        serde_json::json!({"type": "jpeg"}),
+    ] {
+        assert!(serde_json::from_value::<N5Compression>(compression).is_err());
+    }
+    assert!(serde_json::from_value::<N5Compression>(serde_json::json!({"level": 1})).is_err());
+
+    // and plugins with a built-in type are ignored
+    let shadowed = N5Compression::Plugin(
+        serde_json::json!({"type": "gzip"})
+            .as_object()
+            .unwrap()
+            .clone(),
+    );
+    assert!(shadowed.to_bytes_to_bytes_codec().is_err());
+    let n5_meta: N5ArrayMetadata = serde_json::from_value(serde_json::json!({
+        "dimensions": [8],
+        "blockSize": [8],
+        "dataType": "uint8",
+        "compression": {"type": "crc32c"},
+    }))
+    .unwrap();
+    let compression = n5_meta.compression.clone();
+    let mut zarr_meta = n5_meta.try_into_zarr(N5ArrayMode::Default).unwrap();
+    zarr_meta.attributes.remove("_n5");
+    assert_eq!(
+        N5ArrayMetadata::try_from(&zarr_meta).unwrap().compression,
+        compression
+    );
+}
+
+#[test]
+fn test_decode_only_plugin() {
+    let compression: N5Compression =
+        serde_json::from_value(serde_json::json!({"type": "read-only"})).unwrap();
+    assert!(compression.to_bytes_to_bytes_codec().unwrap().is_some());
+    assert!(!compression.can_encode());
+
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    assert!(
+        N5ArrayBuilder::new(vec![8, 8], vec![NonZeroU64::new(8).unwrap(); 2], "uint16")
+            .compression(compression)
+            .build(store, "/")
+            .is_err()
+    );
+    assert!(inner.list().unwrap().is_empty());
+}
+
+#[test]
+fn test_compression_detection() {
+    let data: Vec<u16> = (0..128).collect();
+    let stores = [
+        N5Compression::Raw,
+        N5Compression::Gzip {
+            level: 6,
+            use_zlib: false,
+        },
+    ]
+    .map(|compression| {
+        let inner = Arc::new(MemoryStore::default());
+        let array =
+            N5ArrayBuilder::new(vec![8, 16], vec![NonZeroU64::new(8).unwrap(); 2], "uint16")
+                .compression(compression)
+                .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+                .unwrap();
+        array
+            .store_array_subset(&array.subset_all(), data.as_slice())
+            .unwrap();
+        inner
+    });
+
+    // swap a block between the uncompressed and gzipped arrays
+    let key = "0/1".try_into().unwrap();
+    let raw_block = stores[0].get(&key).unwrap().unwrap();
+    let gzip_block = stores[1].get(&key).unwrap().unwrap();
+    stores[0].set(&key, gzip_block).unwrap();
+    stores[1].set(&key, raw_block).unwrap();
+
+    for inner in stores {
+        let mut store = N5StoreAdapter::new(inner);
+        let array = Array::open(Arc::new(store.clone()), "/").unwrap();
+        assert!(
+            array
+                .retrieve_array_subset::<Vec<u16>>(&array.subset_all())
+                .is_err()
+        );
+
+        assert!(!store.set_compression_detection(true));
+        let array = Array::open(Arc::new(store), "/").unwrap();
+        let decoded: Vec<u16> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+        assert_eq!(decoded, data);
+    }
+}
+
+#[test]
+fn test_compression_detection_raw_like_zlib() {
+    // uncompressed data which starts with a valid zlib header
+    let data: Vec<u8> = [0x78, 0x9c].into_iter().chain(2..16).collect();
+    let stores = [
+        N5Compression::Raw,
+        N5Compression::Gzip {
+            level: 6,
+            use_zlib: false,
+        },
+    ]
+    .map(|compression| {
+        let inner = Arc::new(MemoryStore::default());
+        let array = N5ArrayBuilder::new(vec![16], vec![NonZeroU64::new(16).unwrap()], "uint8")
+            .compression(compression)
+            .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+            .unwrap();
+        array.store_chunk(&[0], data.as_slice()).unwrap();
+        inner
+    });
+
+    let key = "0".try_into().unwrap();
+    let raw_block = stores[0].get(&key).unwrap().unwrap();
+    stores[1].set(&key, raw_block).unwrap();
+    for inner in stores {
+        let mut store = N5StoreAdapter::new(inner);
+        store.set_compression_detection(true);
+        let array = Array::open(Arc::new(store), "/").unwrap();
+        let decoded: Vec<u8> = array.retrieve_chunk(&[0]).unwrap();
+        assert_eq!(decoded, data);
+    }
+}
+
+#[test]
+fn test_compression_detection_magic() {
+    let data: Vec<u16> = (0..64).collect();
+    let write_block = |compression: serde_json::Value| {
+        let inner = Arc::new(MemoryStore::default());
+        let array = N5ArrayBuilder::new(vec![8, 8], vec![NonZeroU64::new(8).unwrap(); 2], "uint16")
+            .compression(serde_json::from_value(compression).unwrap())
+            .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+            .unwrap();
+        array.store_chunk(&[0, 0], data.as_slice()).unwrap();
+        inner
+    };
+
+    let inner = write_block(serde_json::json!({"type": "raw"}));
+    let mut store = N5StoreAdapter::new(inner.clone());
+    store.set_compression_detection(true);
+    let array = Array::open(Arc::new(store), "/").unwrap();
+    let key = "0/0".try_into().unwrap();
+    for compression in [
+        serde_json::json!({"type": "gzip"}),
+        serde_json::json!({"type": "gzip", "useZlib": true}),
+        serde_json::json!({"type": "bzip2"}),
+        serde_json::json!({"type": "zstd"}),
+        serde_json::json!({"type": "blosc", "cname": "zstd", "shuffle": 1}),
+        serde_json::json!({"type": "lz4"}),
+    ] {
+        let block = write_block(compression.clone()).get(&key).unwrap().unwrap();
+        inner.set(&key, block).unwrap();
+        let decoded: Vec<u16> = array.retrieve_chunk(&[0, 0]).unwrap();
+        assert_eq!(decoded, data, "{compression} was not detected");
+    }
+}
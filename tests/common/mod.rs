@@ -0,0 +1,68 @@
+//! Helpers shared between integration tests.
+#![allow(dead_code)]
+
+use npyz::NpyFile;
+use std::path::{Path, PathBuf};
+use zarrs::storage::store::MemoryStore;
+use zarrs::storage::{ListableStorageTraits, StoreKey, WritableStorageTraits};
+
+pub fn data_dir() -> PathBuf {
+    env_logger::try_init().ok();
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("data")
+}
+
+/// Get shape and values from the raw.npy file.
+pub fn read_raw() -> (Vec<u64>, Vec<f32>) {
+    let data = include_bytes!("../../data/raw.npy");
+    let f = NpyFile::new(data.as_slice()).expect("should be valid");
+    let shape = f.shape().to_vec();
+    let data = f
+        .into_vec::<f32>()
+        .expect("should be able to read data to vec");
+    (shape, data)
+}
+
+pub fn read_fs_to_memory(path: impl AsRef<Path>) -> MemoryStore {
+    let store = MemoryStore::default();
+    let root = path.as_ref();
+    let mut dirs_to_visit = vec![PathBuf::from(".")];
+    while let Some(rel_p) = dirs_to_visit.pop() {
+        let p = root.join(&rel_p);
+        for entry in std::fs::read_dir(&p).expect("dir should be readable") {
+            let entry = entry.expect("should be able to read directory entry");
+            let ftype = entry.file_type().expect("should be able to get file type");
+            if ftype.is_file() {
+                let path = entry.path();
+                let rel_path = path
+                    .strip_prefix(root)
+                    .expect("should be able to strip prefix");
+                let key_str = rel_path.to_str().expect("path should be utf-8");
+                let key = StoreKey::new(key_str).expect("key should be valid");
+                let data = std::fs::read(path).expect("should be able to read file");
+                store
+                    .set(&key, data.into())
+                    .expect("should be able to set store");
+            } else if ftype.is_dir() {
+                dirs_to_visit.push(entry.path());
+            } else {
+                panic!("unexpected file type");
+            }
+        }
+    }
+    store
+}
+
+pub fn inner_memory_store(name: &str) -> MemoryStore {
+    let path = data_dir().join(format!("{name}.n5"));
+    read_fs_to_memory(path)
+}
+
+/// Block keys of an N5 array stored at the root of the given store.
+pub fn block_keys(store: &MemoryStore) -> Vec<StoreKey> {
+    store
+        .list()
+        .unwrap()
+        .into_iter()
+        .filter(|k| k.as_str().chars().all(|c| c.is_ascii_digit() || c == '/'))
+        .collect()
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use common::inner_memory_store;
+use zarrs::array::ArrayMetadataV3;
+use zarrs::storage::ReadableStorageTraits;
+use zarrs_n5::{N5ArrayMetadata, N5ArrayMode, N5Compression, N5GroupMetadata, N5Metadata};
+
+fn fixture_metadata(name: &str) -> N5ArrayMetadata {
+    let store = inner_memory_store(name);
+    let bytes = store
+        .get(&"attributes.json".try_into().unwrap())
+        .unwrap()
+        .expect("attributes.json should exist");
+    let N5Metadata::Array(m) = serde_json::from_slice(&bytes).unwrap() else {
+        panic!("fixture should be an array");
+    };
+    m
+}
+
+fn round_trip(n5_meta: &N5ArrayMetadata) -> N5ArrayMetadata {
+    let zarr_meta = n5_meta
+        .clone()
+        .try_into_zarr(N5ArrayMode::Default)
+        .expect("should convert to Zarr");
+    N5ArrayMetadata::try_from(&zarr_meta).expect("should convert back to N5")
+}
+
+#[test]
+fn test_round_trip_fixtures() {
+    for name in [
+        "single_chunk",
+        "even_chunk",
+        "uneven_chunk_padded",
+        "uneven_chunk_truncated",
+        "bz2",
+        "gzip",
+        "zstd",
+        "blosc",
+    ] {
+        let original = fixture_metadata(name);
+        let converted = round_trip(&original);
+        assert_eq!(
+            serde_json::to_value(&converted).unwrap(),
+            serde_json::to_value(&original).unwrap(),
+            "{name} did not round-trip"
+        );
+    }
+}
+
+#[test]
+fn test_round_trip_data_types() {
+    let mut n5_meta = fixture_metadata("single_chunk");
+    for data_type in [
+        "uint8", "int8", "uint16", "int16", "uint32", "int32", "uint64", "int64", "float32",
+        "float64",
+    ] {
+        n5_meta.data_type = data_type.into();
+        assert_eq!(round_trip(&n5_meta).data_type, data_type);
+    }
+}
+
+#[test]
+fn test_without_stash() {
+    let original = fixture_metadata("gzip");
+    let mut zarr_meta: ArrayMetadataV3 = original
+        .clone()
+        .try_into_zarr(N5ArrayMode::Default)
+        .unwrap();
+    zarr_meta.attributes.remove("_n5");
+    let converted = N5ArrayMetadata::try_from(&zarr_meta).unwrap();
+    assert_eq!(converted.dimensions, original.dimensions);
+    assert_eq!(converted.block_size, original.block_size);
+    assert_eq!(converted.data_type, original.data_type);
+    // the implementation default level is made explicit
+    assert_eq!(
+        converted.compression,
+        N5Compression::Gzip {
+            level: 6,
+            use_zlib: false
+        }
+    );
+    assert!(converted.attributes.is_empty());
+}
+
+#[test]
+fn test_use_zlib_round_trip() {
+    let mut original = fixture_metadata("gzip");
+    assert_eq!(
+        original.compression,
+        N5Compression::Gzip {
+            level: -1,
+            use_zlib: false
+        }
+    );
+    original.compression = N5Compression::Gzip {
+        level: 4,
+        use_zlib: true,
+    };
+    assert_eq!(round_trip(&original).compression, original.compression);
+
+    let mut zarr_meta: ArrayMetadataV3 = original
+        .clone()
+        .try_into_zarr(N5ArrayMode::Default)
+        .unwrap();
+    zarr_meta.attributes.remove("_n5");
+    let converted = N5ArrayMetadata::try_from(&zarr_meta).unwrap();
+    assert_eq!(converted.compression, original.compression);
+    assert_eq!(
+        serde_json::to_value(&converted.compression).unwrap(),
+        serde_json::json!({"type": "gzip", "level": 4, "useZlib": true})
+    );
+}
+
+#[test]
+fn test_zstd_parameters_round_trip() {
+    let mut original = fixture_metadata("zstd");
+    original.compression = serde_json::from_value(
+        serde_json::json!({"type": "zstd", "level": 5, "useChecksums": true, "nbWorkers": 4}),
+    )
+    .unwrap();
+    assert_eq!(round_trip(&original).compression, original.compression);
+
+    // without the stash, the checksum setting is recovered from the Zarr codec
+    let mut zarr_meta: ArrayMetadataV3 = original
+        .clone()
+        .try_into_zarr(N5ArrayMode::Default)
+        .unwrap();
+    zarr_meta.attributes.remove("_n5");
+    let converted = N5ArrayMetadata::try_from(&zarr_meta).unwrap();
+    assert_eq!(
+        converted.compression,
+        N5Compression::Zstd {
+            level: 5,
+            use_checksums: true,
+            nb_workers: 0
+        }
+    );
+}
+
+#[test]
+fn test_blosc_typesize_and_nthreads() {
+    let mut original = fixture_metadata("blosc");
+    original.data_type = "uint16".into();
+    original.compression = serde_json::from_value(serde_json::json!({
+        "type": "blosc", "cname": "lz4", "clevel": 5, "shuffle": -1, "nthreads": 4
+    }))
+    .unwrap();
+    let zarr_meta = original
+        .clone()
+        .try_into_zarr(N5ArrayMode::Default)
+        .unwrap();
+    let n5_codec = serde_json::to_value(&zarr_meta.codecs[0]).unwrap();
+    assert_eq!(n5_codec["configuration"]["concurrency"], 4);
+    let blosc = n5_codec["configuration"]["codecs"]
+        .as_array()
+        .unwrap()
+        .last()
+        .unwrap();
+    assert_eq!(blosc["name"], "blosc");
+    assert_eq!(blosc["configuration"]["typesize"], 2);
+    assert_eq!(blosc["configuration"]["shuffle"], "shuffle");
+
+    assert_eq!(round_trip(&original).compression, original.compression);
+
+    // without the stash, nthreads is recovered from the N5 codec
+    let mut zarr_meta = zarr_meta;
+    zarr_meta.attributes.remove("_n5");
+    let N5Compression::Blosc {
+        nthreads, typesize, ..
+    } = N5ArrayMetadata::try_from(&zarr_meta).unwrap().compression
+    else {
+        panic!("should be blosc");
+    };
+    assert_eq!(nthreads, 4);
+    assert_eq!(typesize, Some(2));
+}
+
+#[test]
+fn test_root_version_restored() {
+    let original = fixture_metadata("uneven_chunk_truncated");
+    assert_eq!(original.n5_version.as_deref(), Some("4.0.0"));
+    assert_eq!(round_trip(&original).n5_version.as_deref(), Some("4.0.0"));
+
+    let group = N5GroupMetadata {
+        n5_version: Some("4.0.0".into()),
+        attributes: Default::default(),
+    };
+    let converted: N5GroupMetadata = zarrs::group::GroupMetadataV3::from(group).into();
+    assert_eq!(converted.n5_version.as_deref(), Some("4.0.0"));
+    assert!(converted.attributes.is_empty());
+}
+
+#[test]
+fn test_unrepresentable() {
+    let mut zarr_meta: ArrayMetadataV3 = fixture_metadata("single_chunk")
+        .try_into_zarr(N5ArrayMode::Default)
+        .unwrap();
+    zarr_meta.fill_value = serde_json::from_str("1.0").unwrap();
+    assert!(N5ArrayMetadata::try_from(&zarr_meta).is_err());
+}
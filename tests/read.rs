@@ -1,63 +1,22 @@
-use npyz::NpyFile;
-use std::path::{Path, PathBuf};
+mod common;
+
+use common::{data_dir, inner_memory_store, read_raw};
+use std::borrow::Cow;
+use std::num::NonZeroU64;
 use std::sync::Arc;
+use zarrs::array::codec::api::CodecOptions;
+use zarrs::array::{Array, ArraySubset};
 use zarrs::filesystem::FilesystemStore;
 use zarrs::metadata::v3::NodeMetadataV3;
-use zarrs::storage::WritableStorageTraits;
+use zarrs::storage::storage_adapter::performance_metrics::PerformanceMetricsStorageAdapter;
 use zarrs::storage::store::MemoryStore;
-use zarrs::storage::{ReadableListableStorage, ReadableStorageTraits, StoreKey};
-use zarrs_n5::ImplicitGroupStoreAdapter;
-
-fn data_dir() -> PathBuf {
-    env_logger::try_init().ok();
-    Path::new(env!("CARGO_MANIFEST_DIR")).join("data")
-}
-
-/// Get shape and values from the raw.npy file.
-fn read_raw() -> (Vec<u64>, Vec<f32>) {
-    let data = include_bytes!("../data/raw.npy");
-    let f = NpyFile::new(data.as_slice()).expect("should be valid");
-    let shape = f.shape().to_vec();
-    let data = f
-        .into_vec::<f32>()
-        .expect("should be able to read data to vec");
-    (shape, data)
-}
-
-fn read_fs_to_memory(path: impl AsRef<Path>) -> MemoryStore {
-    let store = MemoryStore::default();
-    let root = path.as_ref();
-    let mut dirs_to_visit = vec![PathBuf::from(".")];
-    while let Some(rel_p) = dirs_to_visit.pop() {
-        let p = root.join(&rel_p);
-        for entry in std::fs::read_dir(&p).expect("dir should be readable") {
-            let entry = entry.expect("should be able to read directory entry");
-            let ftype = entry.file_type().expect("should be able to get file type");
-            if ftype.is_file() {
-                let path = entry.path();
-                let rel_path = path
-                    .strip_prefix(root)
-                    .expect("should be able to strip prefix");
-                let key_str = rel_path.to_str().expect("path should be utf-8");
-                let key = StoreKey::new(key_str).expect("key should be valid");
-                let data = std::fs::read(path).expect("should be able to read file");
-                store
-                    .set(&key, data.into())
-                    .expect("should be able to set store");
-            } else if ftype.is_dir() {
-                dirs_to_visit.push(entry.path());
-            } else {
-                panic!("unexpected file type");
-            }
-        }
-    }
-    store
-}
-
-fn inner_memory_store(name: &str) -> MemoryStore {
-    let path = data_dir().join(format!("{name}.n5"));
-    read_fs_to_memory(path)
-}
+use zarrs::storage::{
+    ReadableListableStorage, ReadableStorageTraits, StoreKey, StorePrefix, WritableStorageTraits,
+};
+use zarrs_n5::{
+    ImplicitGroupStoreAdapter, N5ArrayBuilder, N5ArrayMetadata, N5ArrayMode, N5Compression,
+    N5StoreAdapter,
+};
 
 fn inner_store() -> FilesystemStore {
     let dpath = data_dir();
@@ -197,3 +156,316 @@ fn test_implicit_group_attrs() {
         assert_eq!(s, "bar");
     }
 }
+
+/// A varlength-mode block with the given header shape and big-endian body.
+fn varlength_block(shape: &[u32], num_el: u32, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![0, 1];
+    out.extend_from_slice(&(shape.len() as u16).to_be_bytes());
+    for s in shape {
+        out.extend_from_slice(&s.to_be_bytes());
+    }
+    out.extend_from_slice(&num_el.to_be_bytes());
+    out.extend_from_slice(body);
+    out
+}
+
+#[test]
+fn test_varlength() {
+    let inner = Arc::new(MemoryStore::default());
+    let compression = N5Compression::Gzip {
+        level: 6,
+        use_zlib: false,
+    };
+    N5ArrayBuilder::new(vec![8, 4], vec![NonZeroU64::new(4).unwrap(); 2], "uint64")
+        .compression(compression.clone())
+        .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+        .unwrap();
+
+    let labels: Vec<u64> = vec![1, 2, 300];
+    let body: Vec<u8> = labels.iter().flat_map(|l| l.to_be_bytes()).collect();
+    let body = compression
+        .to_bytes_to_bytes_codec()
+        .unwrap()
+        .unwrap()
+        .encode(Cow::Owned(body), &CodecOptions::default())
+        .unwrap();
+    let gzip_empty = compression
+        .to_bytes_to_bytes_codec()
+        .unwrap()
+        .unwrap()
+        .encode(Cow::Owned(Vec::default()), &CodecOptions::default())
+        .unwrap();
+    inner
+        .set(
+            &"0/0".try_into().unwrap(),
+            varlength_block(&[4, 4], 3, &body).into(),
+        )
+        .unwrap();
+    inner
+        .set(
+            &"1/0".try_into().unwrap(),
+            varlength_block(&[4, 4], 0, &gzip_empty).into(),
+        )
+        .unwrap();
+
+    let mut store = N5StoreAdapter::new(inner.clone());
+    store.set_array_mode(N5ArrayMode::VarLength);
+    let array = Array::open(Arc::new(store), "/").unwrap();
+    // one element per block
+    assert_eq!(array.shape(), &[2, 1]);
+    let blocks: Vec<Vec<u8>> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    let expected: Vec<u8> = labels.iter().flat_map(|l| l.to_ne_bytes()).collect();
+    assert_eq!(blocks, vec![expected, Vec::default()]);
+
+    // a block with the wrong number of elements
+    inner
+        .set(
+            &"1/0".try_into().unwrap(),
+            varlength_block(&[4, 4], 2, &body).into(),
+        )
+        .unwrap();
+    assert!(array.retrieve_chunk::<Vec<Vec<u8>>>(&[1, 0]).is_err());
+
+    // the N5 metadata is restored from the stash
+    let n5_meta: N5ArrayMetadata = serde_json::from_slice(
+        &inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    let zarr_meta = n5_meta
+        .clone()
+        .try_into_zarr(N5ArrayMode::VarLength)
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(N5ArrayMetadata::try_from(&zarr_meta).unwrap()).unwrap(),
+        serde_json::to_value(n5_meta).unwrap()
+    );
+
+    // a resized Zarr array (of one element per block) has N5 dimensions of whole blocks,
+    // so the change is refused like for any other N5 array rather than lost
+    let mut resized = zarr_meta.clone();
+    resized.shape = vec![3, 1];
+    assert_eq!(
+        N5ArrayMetadata::try_from(&resized).unwrap().dimensions,
+        [12, 4]
+    );
+    let mut array = array;
+    array.set_shape(vec![3, 1]).unwrap();
+    assert!(array.store_metadata().is_err());
+
+    // and attribute updates are kept
+    array.set_shape(vec![2, 1]).unwrap();
+    array
+        .attributes_mut()
+        .insert("foo".to_string(), "bar".into());
+    array.store_metadata().unwrap();
+    let n5_meta: N5ArrayMetadata = serde_json::from_slice(
+        &inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(n5_meta.dimensions, [8, 4]);
+    assert_eq!(n5_meta.attributes["foo"], "bar");
+}
+
+#[test]
+fn test_object() {
+    let inner = Arc::new(MemoryStore::default());
+    let compression = N5Compression::Gzip {
+        level: 6,
+        use_zlib: false,
+    };
+    let attributes = serde_json::json!({
+        "dimensions": [8, 4],
+        "blockSize": [4, 4],
+        "dataType": "object",
+        "compression": compression,
+    });
+    inner
+        .set(
+            &"attributes.json".try_into().unwrap(),
+            serde_json::to_vec(&attributes).unwrap().into(),
+        )
+        .unwrap();
+
+    let payload = b"\xac\xed\x00\x05t\x00\x05hello".to_vec();
+    let body = compression
+        .to_bytes_to_bytes_codec()
+        .unwrap()
+        .unwrap()
+        .encode(Cow::Owned(payload.clone()), &CodecOptions::default())
+        .unwrap();
+    // object headers have no shape
+    let mut block = vec![0, 2];
+    block.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    block.extend_from_slice(&body);
+    inner.set(&"1/0".try_into().unwrap(), block.into()).unwrap();
+
+    let mut store = N5StoreAdapter::new(inner.clone());
+    store.set_array_mode(N5ArrayMode::Object);
+    let array = Array::open(Arc::new(store), "/").unwrap();
+    assert_eq!(array.shape(), &[2, 1]);
+    let blocks: Vec<Vec<u8>> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    assert_eq!(blocks, vec![Vec::default(), payload]);
+
+    // varlength blocks are not object blocks
+    inner
+        .set(
+            &"0/0".try_into().unwrap(),
+            varlength_block(&[4, 4], 0, &body).into(),
+        )
+        .unwrap();
+    assert!(array.retrieve_chunk::<Vec<Vec<u8>>>(&[0, 0]).is_err());
+
+    // the N5 metadata is restored from the stash, with the Zarr shape and attributes
+    let n5_meta: N5ArrayMetadata = serde_json::from_value(attributes).unwrap();
+    let mut zarr_meta = n5_meta.clone().try_into_zarr(N5ArrayMode::Object).unwrap();
+    assert_eq!(
+        serde_json::to_value(N5ArrayMetadata::try_from(&zarr_meta).unwrap()).unwrap(),
+        serde_json::to_value(n5_meta).unwrap()
+    );
+    zarr_meta.shape = vec![2, 3];
+    zarr_meta.attributes.insert("foo".to_string(), "bar".into());
+    let restored = N5ArrayMetadata::try_from(&zarr_meta).unwrap();
+    assert_eq!(restored.dimensions, [8, 12]);
+    assert_eq!(restored.attributes["foo"], "bar");
+}
+
+#[test]
+fn test_string_n5_java() {
+    let inner = Arc::new(MemoryStore::default());
+    N5ArrayBuilder::new(vec![9], vec![NonZeroU64::new(3).unwrap()], "string")
+        .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+        .unwrap();
+    // n5-java joins strings with NUL and appends a final NUL;
+    // the header counts bytes, as there are more bytes than elements
+    for (key, body) in [
+        ("0", "a\0déf\0\0".as_bytes()),
+        ("1", b"\0y\0\0"),
+        // without the final NUL, and with trailing empty strings dropped
+        ("2", b"x"),
+    ] {
+        inner
+            .set(
+                &key.try_into().unwrap(),
+                varlength_block(&[3], body.len() as u32, body).into(),
+            )
+            .unwrap();
+    }
+
+    let array = Array::open(Arc::new(N5StoreAdapter::new(inner)), "/").unwrap();
+    let data: Vec<String> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    assert_eq!(data, ["a", "déf", "", "", "y", "", "x", "", ""]);
+}
+
+#[test]
+fn test_mixed_block_modes() {
+    let inner = Arc::new(MemoryStore::default());
+    N5ArrayBuilder::new(vec![4, 4], vec![NonZeroU64::new(2).unwrap(); 2], "uint16")
+        .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+        .unwrap();
+    let store = N5StoreAdapter::new(inner.clone());
+    assert_eq!(store.infer_array_mode(&StorePrefix::root()).unwrap(), None);
+
+    // a full default-mode block, and a varlength block with fewer elements than its shape
+    let mut default_block = vec![0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 2];
+    default_block.extend([1u16, 2, 3, 4].iter().flat_map(|n| n.to_be_bytes()));
+    inner
+        .set(&"0/0".try_into().unwrap(), default_block.into())
+        .unwrap();
+    assert_eq!(
+        store.infer_array_mode(&StorePrefix::root()).unwrap(),
+        Some(N5ArrayMode::Default)
+    );
+    let body: Vec<u8> = [5u16, 6, 7].iter().flat_map(|n| n.to_be_bytes()).collect();
+    inner
+        .set(
+            &"1/0".try_into().unwrap(),
+            varlength_block(&[2, 2], 3, &body).into(),
+        )
+        .unwrap();
+    // only the first block is sampled
+    assert_eq!(
+        store.infer_array_mode(&StorePrefix::root()).unwrap(),
+        Some(N5ArrayMode::Default)
+    );
+
+    // default mode reads the varlength block's elements in N5 order, followed by the fill value
+    let array = Array::open(Arc::new(N5StoreAdapter::new(inner.clone())), "/").unwrap();
+    let subset = ArraySubset::new_with_shape(vec![4, 2]);
+    let data: Vec<u16> = array.retrieve_array_subset(&subset).unwrap();
+    assert_eq!(data, [1, 3, 2, 4, 5, 7, 6, 0]);
+    let subset = ArraySubset::new_with_start_shape(vec![3, 0], vec![1, 2]).unwrap();
+    let data: Vec<u16> = array.retrieve_array_subset(&subset).unwrap();
+    assert_eq!(data, [6, 0]);
+
+    // varlength mode reads each block as a single element, in N5 order
+    let mut store = store;
+    store.set_array_mode(N5ArrayMode::VarLength);
+    let array = Array::open(Arc::new(store), "/").unwrap();
+    let subset = ArraySubset::new_with_shape(vec![2, 1]);
+    let blocks: Vec<Vec<u8>> = array.retrieve_array_subset(&subset).unwrap();
+    let ne_bytes = |ns: &[u16]| ns.iter().flat_map(|n| n.to_ne_bytes()).collect::<Vec<_>>();
+    assert_eq!(blocks, vec![ne_bytes(&[1, 2, 3, 4]), ne_bytes(&[5, 6, 7])]);
+
+    // neither can a varlength block hold more elements than its shape, nor object blocks be read
+    let body: Vec<u8> = [0u16; 5].iter().flat_map(|n| n.to_be_bytes()).collect();
+    inner
+        .set(
+            &"0/1".try_into().unwrap(),
+            varlength_block(&[2, 2], 5, &body).into(),
+        )
+        .unwrap();
+    inner
+        .set(&"1/1".try_into().unwrap(), vec![0, 2, 0, 0, 0, 0].into())
+        .unwrap();
+    let array = Array::open(Arc::new(N5StoreAdapter::new(inner.clone())), "/").unwrap();
+    assert!(array.retrieve_chunk::<Vec<u16>>(&[0, 1]).is_err());
+    assert!(array.retrieve_chunk::<Vec<u16>>(&[1, 1]).is_err());
+}
+
+#[test]
+fn test_partial_decode_raw() {
+    let (raw_shape, raw_data) = read_raw();
+    let inner = Arc::new(PerformanceMetricsStorageAdapter::new(Arc::new(
+        inner_memory_store("uneven_chunk_truncated"),
+    )));
+    let array = Array::open(Arc::new(N5StoreAdapter::new(inner.clone())), "/").unwrap();
+    let block_bytes = 192 * 96 * size_of::<f32>();
+    let expected = |start: [u64; 2], shape: [u64; 2]| -> Vec<f32> {
+        let mut out = Vec::default();
+        for x in start[0]..start[0] + shape[0] {
+            for y in start[1]..start[1] + shape[1] {
+                out.push(raw_data[(x * raw_shape[1] + y) as usize]);
+            }
+        }
+        out
+    };
+
+    // a row of the Zarr view is strided in the column-major block, a column is contiguous
+    for (start, shape) in [([10, 0], [1, 96]), ([0, 10], [192, 1]), ([5, 7], [20, 30])] {
+        let subset = ArraySubset::new_with_start_shape(start.to_vec(), shape.to_vec()).unwrap();
+        inner.reset();
+        let data: Vec<f32> = array.retrieve_array_subset(&subset).unwrap();
+        assert_eq!(data, expected(start, shape));
+        assert!(inner.bytes_read() < block_bytes / 10, "read too much");
+    }
+
+    // a block whose header is smaller than the chunk, with the region beyond it filled
+    let inner = Arc::new(MemoryStore::default());
+    let array = N5ArrayBuilder::new(vec![4, 4], vec![NonZeroU64::new(4).unwrap(); 2], "uint16")
+        .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+        .unwrap();
+    let mut block = vec![0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 2];
+    block.extend([1u16, 2, 3, 4].iter().flat_map(|n| n.to_be_bytes()));
+    inner.set(&"0/0".try_into().unwrap(), block.into()).unwrap();
+    let subset = ArraySubset::new_with_start_shape(vec![1, 0], vec![2, 3]).unwrap();
+    let data: Vec<u16> = array.retrieve_array_subset(&subset).unwrap();
+    assert_eq!(data, [2, 4, 0, 0, 0, 0]);
+    let whole: Vec<u16> = array.retrieve_chunk(&[0, 0]).unwrap();
+    assert_eq!(whole[4..7], data[..3]);
+}
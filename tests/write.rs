@@ -0,0 +1,863 @@
+mod common;
+
+use common::{block_keys, inner_memory_store, read_raw};
+use std::num::NonZeroU64;
+use std::sync::Arc;
+use zarrs::array::chunk_grid::RegularBoundedChunkGrid;
+use zarrs::array::chunk_key_encoding::V2ChunkKeyEncoding;
+use zarrs::array::{Array, ArrayBuilder, ArraySubset, data_type};
+use zarrs::group::GroupBuilder;
+use zarrs::metadata::v3::NodeMetadataV3;
+use zarrs::node::NodePath;
+use zarrs::storage::storage_adapter::performance_metrics::PerformanceMetricsStorageAdapter;
+use zarrs::storage::store::MemoryStore;
+use zarrs::storage::{
+    ListableStorageTraits, ReadableStorageTraits, StoreKey, WritableStorageTraits,
+};
+use zarrs_n5::{
+    N5ArrayBuilder, N5ArrayMetadata, N5ArrayMode, N5BlockHeader, N5Compression, N5DefaultCodec,
+    N5EdgeBlockPolicy, N5Metadata, N5StoreAdapter, create_n5_group, create_n5_root,
+};
+
+/// Copy a fixture into memory, add Zarr metadata, and delete its blocks.
+///
+/// Returns the store and the original blocks.
+fn emptied_fixture(
+    name: &str,
+    edge_block_policy: N5EdgeBlockPolicy,
+) -> (Arc<MemoryStore>, Vec<(StoreKey, Vec<u8>)>) {
+    let store = Arc::new(inner_memory_store(name));
+    let n5_bytes = store
+        .get(&"attributes.json".try_into().unwrap())
+        .unwrap()
+        .expect("attributes.json should exist");
+    let N5Metadata::Array(n5_meta) = serde_json::from_slice(&n5_bytes).unwrap() else {
+        panic!("fixture should be an array");
+    };
+    let zarr_meta = NodeMetadataV3::Array(
+        n5_meta
+            .try_into_zarr_with_edge_block_policy(N5ArrayMode::Default, edge_block_policy)
+            .expect("should be able to convert metadata"),
+    );
+    store
+        .set(
+            &"zarr.json".try_into().unwrap(),
+            serde_json::to_vec(&zarr_meta).unwrap().into(),
+        )
+        .unwrap();
+
+    let mut blocks = Vec::default();
+    for key in block_keys(&store) {
+        let value = store.get(&key).unwrap().unwrap();
+        blocks.push((key.clone(), value.to_vec()));
+        store.erase(&key).unwrap();
+    }
+    assert!(!blocks.is_empty());
+    (store, blocks)
+}
+
+fn write_raw(store: Arc<MemoryStore>) {
+    let (_, raw_data) = read_raw();
+    let array = Array::open(store, "/").expect("open array");
+    array
+        .store_array_subset(&array.subset_all(), raw_data.as_slice())
+        .expect("store all data");
+}
+
+/// Uncompressed blocks written by the codec should be identical to those written by tensorstore/ n5-java.
+fn check_write_identical(name: &str, edge_block_policy: N5EdgeBlockPolicy) {
+    let (store, blocks) = emptied_fixture(name, edge_block_policy);
+    write_raw(store.clone());
+    for (key, expected) in blocks {
+        let actual = store.get(&key).unwrap().expect("block should be written");
+        assert_eq!(actual.to_vec(), expected, "block {key} differs");
+    }
+}
+
+/// Compressed blocks may not be byte-identical, but should round-trip.
+fn check_write_round_trip(name: &str) {
+    let (store, _) = emptied_fixture(name, N5EdgeBlockPolicy::Truncate);
+    write_raw(store.clone());
+    let (raw_shape, raw_data) = read_raw();
+    let array = Array::open(store, "/").expect("open array");
+    assert_eq!(array.shape(), raw_shape.as_slice());
+    let data: Vec<f32> = array
+        .retrieve_array_subset(&array.subset_all())
+        .expect("retrieve all data");
+    assert_eq!(data, raw_data);
+}
+
+#[test]
+fn test_write_single_chunk() {
+    check_write_identical("single_chunk", N5EdgeBlockPolicy::Truncate);
+}
+
+#[test]
+fn test_write_even_chunk() {
+    check_write_identical("even_chunk", N5EdgeBlockPolicy::Truncate);
+}
+
+#[test]
+fn test_write_uneven_truncated() {
+    check_write_identical("uneven_chunk_truncated", N5EdgeBlockPolicy::Truncate);
+}
+
+#[test]
+fn test_write_uneven_padded() {
+    check_write_identical("uneven_chunk_padded", N5EdgeBlockPolicy::Pad);
+}
+
+#[test]
+fn test_write_gzip() {
+    check_write_round_trip("gzip");
+}
+
+#[test]
+fn test_write_bz2() {
+    check_write_round_trip("bz2");
+}
+
+#[test]
+fn test_write_zstd() {
+    check_write_round_trip("zstd");
+}
+
+#[test]
+fn test_write_blosc() {
+    check_write_round_trip("blosc");
+}
+
+#[test]
+fn test_builder_through_adapter() {
+    let (raw_shape, raw_data) = read_raw();
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+
+    let block_size: Vec<NonZeroU64> = [192, 96]
+        .into_iter()
+        .map(|n| NonZeroU64::new(n).unwrap())
+        .collect();
+    let chunk_grid = RegularBoundedChunkGrid::new(raw_shape.clone(), block_size.clone()).unwrap();
+    let compression = N5Compression::Gzip {
+        level: 6,
+        use_zlib: false,
+    };
+    let array = ArrayBuilder::new_with_chunk_grid(chunk_grid, data_type::float32(), 0.0f32)
+        .array_to_bytes_codec(Arc::new(N5DefaultCodec::new(
+            compression.to_bytes_to_bytes_codec().unwrap(),
+            raw_shape.len(),
+        )))
+        .chunk_key_encoding(V2ChunkKeyEncoding::new_slash())
+        .attributes(
+            serde_json::json!({"foo": "bar"})
+                .as_object()
+                .unwrap()
+                .clone(),
+        )
+        .build(store.clone(), "/arr")
+        .expect("build array");
+    array.store_metadata().expect("store metadata");
+    array
+        .store_array_subset(&array.subset_all(), raw_data.as_slice())
+        .expect("store all data");
+
+    GroupBuilder::new()
+        .build(store.clone(), "/")
+        .unwrap()
+        .store_metadata()
+        .expect("store group metadata");
+
+    assert!(
+        inner
+            .get(&"arr/zarr.json".try_into().unwrap())
+            .unwrap()
+            .is_none()
+    );
+    let n5_bytes = inner
+        .get(&"arr/attributes.json".try_into().unwrap())
+        .unwrap()
+        .expect("attributes.json should be written");
+    let N5Metadata::Array(n5_meta) = serde_json::from_slice(&n5_bytes).unwrap() else {
+        panic!("should be array metadata");
+    };
+    assert_eq!(n5_meta.dimensions, raw_shape);
+    assert_eq!(n5_meta.block_size, block_size);
+    assert_eq!(n5_meta.data_type, "float32");
+    assert_eq!(n5_meta.compression, compression);
+    assert_eq!(n5_meta.attributes.get("foo").unwrap(), "bar");
+    assert!(
+        inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .is_some()
+    );
+    let header =
+        N5BlockHeader::from_bytes(&inner.get(&"arr/1/1".try_into().unwrap()).unwrap().unwrap())
+            .unwrap();
+    assert_eq!(header.to_bytes()[4..], [0, 0, 0, 64, 0, 0, 0, 32]);
+
+    let reopened = Array::open(store, "/arr").expect("reopen array");
+    let data: Vec<f32> = reopened
+        .retrieve_array_subset(&reopened.subset_all())
+        .expect("retrieve all data");
+    assert_eq!(data, raw_data);
+}
+
+fn get_json(store: &MemoryStore, key: &str) -> serde_json::Map<String, serde_json::Value> {
+    let bytes = store
+        .get(&key.try_into().unwrap())
+        .unwrap()
+        .expect("key should exist");
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[test]
+fn test_merge_attributes() {
+    let inner = Arc::new(inner_memory_store("uneven_chunk_truncated"));
+    let original = get_json(&inner, "attributes.json");
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+
+    let mut array = Array::open(store.clone(), "/").expect("open array");
+
+    // another tool writes an attribute after we have opened the array
+    let mut late = original.clone();
+    late.insert("late".into(), "other tool".into());
+    inner
+        .set(
+            &"attributes.json".try_into().unwrap(),
+            serde_json::to_vec(&late).unwrap().into(),
+        )
+        .unwrap();
+
+    array
+        .attributes_mut()
+        .insert("foo".into(), serde_json::json!({"bar": 1}));
+    array.store_metadata().expect("store metadata");
+
+    let merged = get_json(&inner, "attributes.json");
+    assert_eq!(merged.get("late").unwrap(), "other tool");
+    assert_eq!(merged.get("foo").unwrap(), &serde_json::json!({"bar": 1}));
+    for key in ["n5", "dimensions", "blockSize", "dataType", "compression"] {
+        assert_eq!(merged.get(key), original.get(key), "{key} changed");
+    }
+}
+
+#[test]
+fn test_merge_keeps_equivalent_compression() {
+    let inner = Arc::new(inner_memory_store("gzip"));
+    let original = get_json(&inner, "attributes.json");
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+
+    let array = Array::open(store, "/").expect("open array");
+    array.store_metadata().expect("store metadata");
+
+    let merged = get_json(&inner, "attributes.json");
+    assert_eq!(merged.get("compression"), original.get("compression"));
+}
+
+#[test]
+fn test_merge_refuses_structural_conflicts() {
+    let inner = Arc::new(inner_memory_store("single_chunk"));
+    let original = get_json(&inner, "attributes.json");
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+
+    let mut array = Array::open(store.clone(), "/").expect("open array");
+    array
+        .attributes_mut()
+        .insert("dataType".into(), "uint8".into());
+    assert!(array.store_metadata().is_err());
+
+    let array = ArrayBuilder::new_with_chunk_grid(
+        RegularBoundedChunkGrid::new(vec![10, 10], vec![NonZeroU64::new(5).unwrap(); 2]).unwrap(),
+        data_type::float32(),
+        0.0f32,
+    )
+    .array_to_bytes_codec(Arc::new(N5DefaultCodec::new(None, 2)))
+    .chunk_key_encoding(V2ChunkKeyEncoding::new_slash())
+    .build(store.clone(), "/")
+    .unwrap();
+    assert!(array.store_metadata().is_err());
+
+    let group = GroupBuilder::new().build(store, "/").unwrap();
+    assert!(group.store_metadata().is_err());
+
+    assert_eq!(get_json(&inner, "attributes.json"), original);
+}
+
+#[test]
+fn test_create_hierarchy() {
+    let store = Arc::new(MemoryStore::default());
+    let root = NodePath::new("/container").unwrap();
+    create_n5_root(
+        store.as_ref(),
+        &root,
+        serde_json::json!({"foo": "bar"})
+            .as_object()
+            .unwrap()
+            .clone(),
+    )
+    .expect("create root");
+    let root_attrs = get_json(&store, "container/attributes.json");
+    assert_eq!(root_attrs.get("n5").unwrap(), zarrs_n5::N5_VERSION);
+    assert_eq!(root_attrs.get("foo").unwrap(), "bar");
+
+    create_n5_group(
+        store.as_ref(),
+        &NodePath::new("/container/a/b").unwrap(),
+        None,
+    )
+    .expect("create group");
+    assert!(get_json(&store, "container/a/b/attributes.json").is_empty());
+    assert!(
+        store
+            .get(&"container/a/attributes.json".try_into().unwrap())
+            .unwrap()
+            .is_none()
+    );
+
+    // re-creating the root merges attributes
+    create_n5_root(
+        store.as_ref(),
+        &root,
+        serde_json::json!({"baz": 1}).as_object().unwrap().clone(),
+    )
+    .expect("re-create root");
+    let root_attrs = get_json(&store, "container/attributes.json");
+    assert_eq!(root_attrs.get("foo").unwrap(), "bar");
+    assert_eq!(root_attrs.get("baz").unwrap(), 1);
+
+    assert!(
+        create_n5_root(
+            store.as_ref(),
+            &NodePath::new("/container/a/c").unwrap(),
+            Default::default()
+        )
+        .is_err()
+    );
+
+    let adapter = Arc::new(N5StoreAdapter::new(store.clone()));
+    zarrs::group::Group::open(adapter, "/container/a/b").expect("open group through adapter");
+}
+
+#[test]
+fn test_create_group_over_array_fails() {
+    let inner = inner_memory_store("single_chunk");
+    assert!(create_n5_group(&inner, &NodePath::root(), None).is_err());
+}
+
+#[test]
+fn test_n5_array_builder() {
+    let (raw_shape, raw_data) = read_raw();
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    create_n5_root(inner.as_ref(), &NodePath::root(), Default::default()).unwrap();
+
+    // level -1 has no exact Zarr equivalent, so should be preserved as written
+    let compression = N5Compression::Gzip {
+        level: -1,
+        use_zlib: false,
+    };
+    let block_size: Vec<NonZeroU64> = [192, 96]
+        .into_iter()
+        .map(|n| NonZeroU64::new(n).unwrap())
+        .collect();
+    let array = N5ArrayBuilder::new(raw_shape.clone(), block_size.clone(), "float32")
+        .compression(compression.clone())
+        .attributes(
+            serde_json::json!({"foo": "bar"})
+                .as_object()
+                .unwrap()
+                .clone(),
+        )
+        .build(store.clone(), "/arr")
+        .expect("build array");
+    array
+        .store_array_subset(&array.subset_all(), raw_data.as_slice())
+        .expect("store all data");
+
+    let attrs = get_json(&inner, "arr/attributes.json");
+    assert_eq!(
+        serde_json::Value::Object(attrs),
+        serde_json::json!({
+            "dimensions": raw_shape,
+            "blockSize": [192, 96],
+            "dataType": "float32",
+            "compression": {"type": "gzip", "level": -1, "useZlib": false},
+            "foo": "bar",
+        })
+    );
+    let header =
+        N5BlockHeader::from_bytes(&inner.get(&"arr/1/1".try_into().unwrap()).unwrap().unwrap())
+            .unwrap();
+    assert_eq!(header.to_bytes()[4..], [0, 0, 0, 64, 0, 0, 0, 32]);
+
+    let reopened = Array::open(store, "/arr").expect("reopen array");
+    let data: Vec<f32> = reopened
+        .retrieve_array_subset(&reopened.subset_all())
+        .expect("retrieve all data");
+    assert_eq!(data, raw_data);
+}
+
+#[test]
+fn test_n5_array_builder_rejects_invalid() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let block_size = vec![NonZeroU64::new(5).unwrap(); 2];
+
+    let mut builder = N5ArrayBuilder::new(vec![10, 10], block_size, "uint8");
+    builder.compression(N5Compression::Xz { preset: 10 });
+    assert!(builder.build(store.clone(), "/arr").is_err());
+
+    builder.compression(N5Compression::Raw);
+    assert!(
+        N5ArrayBuilder::new(vec![10], vec![NonZeroU64::new(5).unwrap(); 2], "uint8")
+            .build(store.clone(), "/arr")
+            .is_err()
+    );
+    assert!(
+        N5ArrayBuilder::new(vec![10, 10], vec![NonZeroU64::new(5).unwrap(); 2], "bool")
+            .build(store.clone(), "/arr")
+            .is_err()
+    );
+    // decode-only
+    assert!(
+        N5ArrayBuilder::new(vec![10, 10], vec![NonZeroU64::new(5).unwrap(); 2], "uint8")
+            .compression(N5Compression::Jpeg { quality: 90 })
+            .build(store.clone(), "/arr")
+            .is_err()
+    );
+    assert!(inner.list().unwrap().is_empty());
+
+    builder.build(store, "/arr").expect("build valid array");
+}
+
+#[test]
+fn test_erase_chunk() {
+    let inner = Arc::new(inner_memory_store("uneven_chunk_truncated"));
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let array = Array::open(store, "/").expect("open array");
+    assert!(inner.get(&"1/1".try_into().unwrap()).unwrap().is_some());
+    array.erase_chunk(&[1, 1]).expect("erase chunk");
+    assert!(inner.get(&"1/1".try_into().unwrap()).unwrap().is_none());
+    assert!(inner.get(&"0/0".try_into().unwrap()).unwrap().is_some());
+}
+
+#[test]
+fn test_erase_nodes() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    create_n5_root(inner.as_ref(), &NodePath::root(), Default::default()).unwrap();
+    let block_size = vec![NonZeroU64::new(2).unwrap(); 2];
+    // numeric child names look like block keys
+    for path in ["/parent", "/parent/0", "/parent/0/1"] {
+        let array = N5ArrayBuilder::new(vec![4, 4], block_size.clone(), "uint8")
+            .build(store.clone(), path)
+            .expect("build array");
+        array
+            .store_array_subset(&array.subset_all(), vec![1u8; 16])
+            .unwrap();
+    }
+    create_n5_group(inner.as_ref(), &NodePath::new("/group").unwrap(), None).unwrap();
+    let parent_keys = |inner: &MemoryStore| -> Vec<String> {
+        inner
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|k| k.to_string())
+            .filter(|k| k.starts_with("parent/"))
+            .collect()
+    };
+    let before = parent_keys(&inner);
+
+    Array::open(store.clone(), "/parent/0")
+        .unwrap()
+        .erase_metadata()
+        .expect("erase array");
+    let after = parent_keys(&inner);
+    let erased: Vec<_> = before.iter().filter(|k| !after.contains(k)).collect();
+    // blocks sharing a directory with a child node are ambiguous, so are left in place
+    assert_eq!(
+        erased,
+        ["parent/0/0/0", "parent/0/0/1", "parent/0/attributes.json"]
+    );
+    // the child array is intact
+    let child = Array::open(store.clone(), "/parent/0/1").expect("open child");
+    let data: Vec<u8> = child.retrieve_array_subset(&child.subset_all()).unwrap();
+    assert_eq!(data, vec![1u8; 16]);
+
+    zarrs::group::Group::open(store.clone(), "/group")
+        .unwrap()
+        .erase_metadata()
+        .expect("erase group");
+    assert!(
+        inner
+            .get(&"group/attributes.json".try_into().unwrap())
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        inner
+            .get(&"attributes.json".try_into().unwrap())
+            .unwrap()
+            .is_some()
+    );
+}
+
+fn header_shape(store: &MemoryStore, key: &str) -> Vec<u32> {
+    let block = store.get(&key.try_into().unwrap()).unwrap().unwrap();
+    let header = N5BlockHeader::from_bytes(&block).unwrap();
+    header.to_bytes()[4..]
+        .chunks(4)
+        .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+        .collect()
+}
+
+fn resize_fixture(
+    edge_block_policy: N5EdgeBlockPolicy,
+) -> (Arc<MemoryStore>, Arc<N5StoreAdapter<Arc<MemoryStore>>>) {
+    let inner = Arc::new(MemoryStore::default());
+    let mut adapter = N5StoreAdapter::new(inner.clone());
+    adapter.set_edge_block_policy(edge_block_policy);
+    let store = Arc::new(adapter);
+    let array = N5ArrayBuilder::new(vec![10, 10], vec![NonZeroU64::new(4).unwrap(); 2], "uint8")
+        .edge_block_policy(edge_block_policy)
+        .build(store.clone(), "/")
+        .unwrap();
+    let data: Vec<u8> = (1..=100).collect();
+    array.store_array_subset(&array.subset_all(), data).unwrap();
+    (inner, store)
+}
+
+#[test]
+fn test_resize_shrink() {
+    let (inner, store) = resize_fixture(N5EdgeBlockPolicy::Truncate);
+    assert_eq!(header_shape(&inner, "1/2"), [4, 2]);
+
+    let array = zarrs_n5::resize_n5_array(store.clone(), "/", vec![6, 7], true).unwrap();
+    assert_eq!(
+        get_json(&inner, "attributes.json")["dimensions"],
+        serde_json::json!([6, 7])
+    );
+    assert!(inner.get(&"2/0".try_into().unwrap()).unwrap().is_none());
+    assert!(inner.get(&"0/2".try_into().unwrap()).unwrap().is_none());
+    assert_eq!(header_shape(&inner, "0/0"), [4, 4]);
+    assert_eq!(header_shape(&inner, "1/0"), [2, 4]);
+    assert_eq!(header_shape(&inner, "1/1"), [2, 3]);
+
+    let expected: Vec<u8> = (0..6u8)
+        .flat_map(|i| (0..7u8).map(move |j| i * 10 + j + 1))
+        .collect();
+    let reopened = Array::open(store, "/").unwrap();
+    for a in [array, reopened] {
+        let data: Vec<u8> = a.retrieve_array_subset(&a.subset_all()).unwrap();
+        assert_eq!(data, expected);
+    }
+}
+
+fn check_resize_grow_and_keep_blocks(edge_block_policy: N5EdgeBlockPolicy) {
+    let (inner, store) = resize_fixture(edge_block_policy);
+
+    // shrinking without pruning leaves out-of-bounds blocks in place
+    zarrs_n5::resize_n5_array(store.clone(), "/", vec![6, 10], false).unwrap();
+    assert!(inner.get(&"2/0".try_into().unwrap()).unwrap().is_some());
+    let edge_shape = match edge_block_policy {
+        N5EdgeBlockPolicy::Truncate => [2, 2],
+        N5EdgeBlockPolicy::Pad => [4, 4],
+    };
+    assert_eq!(header_shape(&inner, "1/2"), edge_shape);
+
+    // elements cut off by the shrink are not restored
+    let array = zarrs_n5::resize_n5_array(store, "/", vec![12, 12], false).unwrap();
+    let data: Vec<u8> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    for i in 0..12u8 {
+        for j in 0..12u8 {
+            let expected = if j < 10 && (i < 6 || (8..10).contains(&i)) {
+                i * 10 + j + 1
+            } else {
+                0
+            };
+            assert_eq!(
+                data[usize::from(i) * 12 + usize::from(j)],
+                expected,
+                "{i}, {j}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_resize_grow_and_keep_blocks() {
+    check_resize_grow_and_keep_blocks(N5EdgeBlockPolicy::Truncate);
+}
+
+#[test]
+fn test_resize_grow_and_keep_blocks_padded() {
+    check_resize_grow_and_keep_blocks(N5EdgeBlockPolicy::Pad);
+}
+
+#[test]
+fn test_resize_padded() {
+    let (inner, store) = resize_fixture(N5EdgeBlockPolicy::Pad);
+    zarrs_n5::resize_n5_array(store, "/", vec![6, 7], true).unwrap();
+    assert_eq!(header_shape(&inner, "1/1"), [4, 4]);
+}
+
+#[test]
+fn test_fill_value_blocks_not_written() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let array = N5ArrayBuilder::new(vec![8, 8], vec![NonZeroU64::new(4).unwrap(); 2], "uint16")
+        .compression(N5Compression::Gzip {
+            level: 6,
+            use_zlib: false,
+        })
+        .build(store, "/")
+        .unwrap();
+
+    let mut data = vec![0u16; 64];
+    data[0] = 1;
+    array
+        .store_array_subset(&array.subset_all(), data.as_slice())
+        .unwrap();
+    assert_eq!(block_keys(&inner), [StoreKey::new("0/0").unwrap()]);
+
+    // overwriting a block with the fill value erases it
+    array.store_chunk(&[0, 0], vec![0u16; 16]).unwrap();
+    assert!(block_keys(&inner).is_empty());
+    let data: Vec<u16> = array.retrieve_array_subset(&array.subset_all()).unwrap();
+    assert_eq!(data, vec![0u16; 64]);
+
+    // unless explicitly requested
+    array
+        .store_chunk_opt(
+            &[1, 1],
+            vec![0u16; 16],
+            &zarrs::array::codec::api::CodecOptions::default().with_store_empty_chunks(true),
+        )
+        .unwrap();
+    assert_eq!(block_keys(&inner), [StoreKey::new("1/1").unwrap()]);
+}
+
+fn partial_fixture(
+    compression: N5Compression,
+    edge_block_policy: N5EdgeBlockPolicy,
+) -> (Arc<MemoryStore>, Array<N5StoreAdapter<Arc<MemoryStore>>>) {
+    let inner = Arc::new(MemoryStore::default());
+    let mut adapter = N5StoreAdapter::new(inner.clone());
+    adapter.set_edge_block_policy(edge_block_policy);
+    let array = N5ArrayBuilder::new(vec![10, 10], vec![NonZeroU64::new(4).unwrap(); 2], "uint16")
+        .compression(compression)
+        .edge_block_policy(edge_block_policy)
+        .build(Arc::new(adapter), "/")
+        .unwrap();
+    let data: Vec<u16> = (1..=100).collect();
+    array.store_array_subset(&array.subset_all(), data).unwrap();
+    (inner, array)
+}
+
+fn partial_encoding() -> zarrs::array::codec::api::CodecOptions {
+    zarrs::array::codec::api::CodecOptions::default().with_experimental_partial_encoding(true)
+}
+
+#[test]
+fn test_partial_encode_matches_full() {
+    for compression in [
+        N5Compression::Raw,
+        N5Compression::Gzip {
+            level: 6,
+            use_zlib: false,
+        },
+    ] {
+        for policy in [N5EdgeBlockPolicy::Truncate, N5EdgeBlockPolicy::Pad] {
+            let (partial_inner, partial) = partial_fixture(compression.clone(), policy);
+            let (full_inner, full) = partial_fixture(compression.clone(), policy);
+            let writes: [(&[u64], _); 3] = [
+                (&[0, 0], ArraySubset::new_with_ranges(&[1..3, 1..4])),
+                (&[2, 1], ArraySubset::new_with_ranges(&[1..2, 0..4])),
+                (&[2, 2], ArraySubset::new_with_ranges(&[0..2, 1..2])),
+            ];
+            for (chunk, subset) in writes {
+                let data = vec![1000u16; subset.num_elements_usize()];
+                partial
+                    .store_chunk_subset_opt(chunk, &subset, data.clone(), &partial_encoding())
+                    .unwrap();
+                full.store_chunk_subset(chunk, &subset, data).unwrap();
+            }
+            assert_eq!(partial_inner.list().unwrap(), full_inner.list().unwrap());
+            for key in block_keys(&full_inner) {
+                assert_eq!(
+                    partial_inner.get(&key).unwrap(),
+                    full_inner.get(&key).unwrap(),
+                    "{compression:?}, {policy:?}, {key}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_partial_encode_keeps_header_shape() {
+    for compression in [
+        N5Compression::Raw,
+        N5Compression::Gzip {
+            level: 6,
+            use_zlib: false,
+        },
+    ] {
+        let (inner, _) = partial_fixture(compression, N5EdgeBlockPolicy::Pad);
+        // reopen with the default policy, which would truncate rewritten edge blocks
+        let array = Array::open(Arc::new(N5StoreAdapter::new(inner.clone())), "/").unwrap();
+        let subset = ArraySubset::new_with_ranges(&[1..2, 0..2]);
+        array
+            .store_chunk_subset_opt(&[2, 2], &subset, vec![0u16, 1000], &partial_encoding())
+            .unwrap();
+        assert_eq!(header_shape(&inner, "2/2"), [4, 4]);
+
+        let data: Vec<u16> = array
+            .retrieve_array_subset(&ArraySubset::new_with_ranges(&[8..10, 8..10]))
+            .unwrap();
+        assert_eq!(data, [89, 90, 0, 1000]);
+
+        // an existing block overwritten with the fill value is erased
+        array
+            .store_chunk_subset_opt(
+                &[2, 2],
+                &array
+                    .chunk_subset(&[2, 2])
+                    .unwrap()
+                    .relative_to(&[8, 8])
+                    .unwrap(),
+                vec![0u16; 4],
+                &partial_encoding(),
+            )
+            .unwrap();
+        assert!(inner.get(&"2/2".try_into().unwrap()).unwrap().is_none());
+    }
+}
+
+#[test]
+fn test_partial_encode_erases_fill_value_block() {
+    let (inner, array) = partial_fixture(N5Compression::Raw, N5EdgeBlockPolicy::Truncate);
+    let mut data = vec![0u16; 16];
+    data[5] = 1;
+    array.store_chunk(&[0, 0], data).unwrap();
+    assert!(inner.get(&"0/0".try_into().unwrap()).unwrap().is_some());
+
+    // an uncompressed block could be updated in place, but the update leaves only the fill value
+    array
+        .store_chunk_subset_opt(
+            &[0, 0],
+            &ArraySubset::new_with_ranges(&[1..2, 1..2]),
+            vec![0u16],
+            &partial_encoding(),
+        )
+        .unwrap();
+    assert!(inner.get(&"0/0".try_into().unwrap()).unwrap().is_none());
+    let data: Vec<u16> = array
+        .retrieve_array_subset(&ArraySubset::new_with_ranges(&[0..4, 0..4]))
+        .unwrap();
+    assert_eq!(data, vec![0u16; 16]);
+}
+
+#[test]
+fn test_partial_encode_in_place_reads_header() {
+    let inner = Arc::new(PerformanceMetricsStorageAdapter::new(Arc::new(
+        MemoryStore::default(),
+    )));
+    let array = N5ArrayBuilder::new(
+        vec![64, 64],
+        vec![NonZeroU64::new(64).unwrap(); 2],
+        "uint16",
+    )
+    .build(Arc::new(N5StoreAdapter::new(inner.clone())), "/")
+    .unwrap();
+    let data: Vec<u16> = (1..=64 * 64).collect();
+    array.store_chunk(&[0, 0], data).unwrap();
+
+    inner.reset();
+    let subset = ArraySubset::new_with_ranges(&[3..4, 5..7]);
+    array
+        .store_chunk_subset_opt(&[0, 0], &subset, vec![0u16, 1000], &partial_encoding())
+        .unwrap();
+    assert!(inner.bytes_read() < 64, "read too much");
+    assert_eq!(inner.bytes_written(), 4);
+    let data: Vec<u16> = array.retrieve_array_subset(&subset).unwrap();
+    assert_eq!(data, [0, 1000]);
+
+    // with compression detection, an uncompressed array may hold compressed blocks, so blocks are rewritten whole
+    let mut store = N5StoreAdapter::new(inner.clone());
+    store.set_compression_detection(true);
+    let array = Array::open(Arc::new(store), "/").unwrap();
+    let gzip_inner = Arc::new(MemoryStore::default());
+    let gzip = N5ArrayBuilder::new(
+        vec![64, 64],
+        vec![NonZeroU64::new(64).unwrap(); 2],
+        "uint16",
+    )
+    .compression(N5Compression::Gzip {
+        level: 6,
+        use_zlib: false,
+    })
+    .build(Arc::new(N5StoreAdapter::new(gzip_inner.clone())), "/")
+    .unwrap();
+    gzip.store_chunk(&[0, 0], vec![7u16; 64 * 64]).unwrap();
+    let key = "0/0".try_into().unwrap();
+    inner
+        .set(&key, gzip_inner.get(&key).unwrap().unwrap())
+        .unwrap();
+    array
+        .store_chunk_subset_opt(&[0, 0], &subset, vec![0u16, 1000], &partial_encoding())
+        .unwrap();
+    let data: Vec<u16> = array.retrieve_chunk(&[0, 0]).unwrap();
+    assert_eq!(&data[..3 * 64 + 5], vec![7u16; 3 * 64 + 5]);
+    assert_eq!(&data[3 * 64 + 5..3 * 64 + 8], [0, 1000, 7]);
+}
+
+#[test]
+fn test_string() {
+    let inner = Arc::new(MemoryStore::default());
+    let store = Arc::new(N5StoreAdapter::new(inner.clone()));
+    let array = N5ArrayBuilder::new(vec![3, 2], vec![NonZeroU64::new(2).unwrap(); 2], "string")
+        .build(store.clone(), "/")
+        .unwrap();
+    assert_eq!(array.fill_value().as_ne_bytes(), b"");
+    let attrs = serde_json::Value::Object(get_json(&inner, "attributes.json"));
+    let n5_meta: N5ArrayMetadata = serde_json::from_value(attrs.clone()).unwrap();
+    let zarr_meta = n5_meta.try_into_zarr(N5ArrayMode::Default).unwrap();
+    let round_tripped = N5ArrayMetadata::try_from(&zarr_meta).unwrap();
+    assert_eq!(serde_json::to_value(round_tripped).unwrap(), attrs);
+
+    // C order over [x, y]
+    let data: Vec<String> = ["a", "bc", "", "déf", "x", ""]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    array
+        .store_array_subset(&array.subset_all(), data.clone())
+        .unwrap();
+
+    // N5 blocks are x-fastest, each string is followed by NUL as in n5-java,
+    // and headers count bytes rather than elements when they differ
+    let block = inner.get(&"0/0".try_into().unwrap()).unwrap().unwrap();
+    let mut expected = vec![0, 1, 0, 2, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 11];
+    expected.extend_from_slice("a\0\0bc\0déf\0".as_bytes());
+    assert_eq!(block.to_vec(), expected);
+    let block = inner.get(&"1/0".try_into().unwrap()).unwrap().unwrap();
+    assert_eq!(
+        block.to_vec(),
+        [0, 1, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, b'x', 0, 0]
+    );
+
+    let reopened = Array::open(store, "/").unwrap();
+    let read: Vec<String> = reopened
+        .retrieve_array_subset(&reopened.subset_all())
+        .unwrap();
+    assert_eq!(read, data);
+
+    // missing blocks are read as empty strings
+    reopened.erase_chunk(&[0, 0]).unwrap();
+    let read: Vec<String> = reopened
+        .retrieve_array_subset(&reopened.subset_all())
+        .unwrap();
+    assert_eq!(read, ["", "", "", "", "x", ""]);
+}